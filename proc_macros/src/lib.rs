@@ -7,21 +7,76 @@ use syn::{
     *,
 };
 
-#[proc_macro_derive(Component)]
+// A lifecycle hook declared with `#[component(on_add = path)]`
+struct HookArg {
+    name: Ident,
+    path: Path,
+}
+
+impl Parse for HookArg {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let name = input.parse::<Ident>()?;
+        input.parse::<Token![=]>()?;
+
+        let path = input.parse::<Path>()?;
+        Ok(HookArg { name, path })
+    }
+}
+
+fn parse_hooks(attrs: &[Attribute]) -> Result<Vec<HookArg>> {
+    let mut hooks = Vec::new();
+
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("component")) {
+        let args = attr.parse_args_with(Punctuated::<HookArg, Token![,]>::parse_terminated)?;
+
+        for arg in args {
+            match arg.name.to_string().as_str() {
+                "on_add" | "on_insert" | "on_replace" | "on_remove" => hooks.push(arg),
+                _ => return Err(Error::new_spanned(&arg.name, "Unknown component hook")),
+            }
+        }
+    }
+
+    Ok(hooks)
+}
+
+#[proc_macro_derive(Component, attributes(component))]
 pub fn derive_component(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
+    let hooks = match parse_hooks(&input.attrs) {
+        Ok(hooks) => hooks,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
     let ident = input.ident;
 
     let generics = input.generics;
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
+    let declared_hooks = if hooks.is_empty() {
+        quote! {}
+    } else {
+        let names = hooks.iter().map(|hook| &hook.name);
+        let paths = hooks.iter().map(|hook| &hook.path);
+
+        quote! {
+            fn declared_hooks(&self) -> Option<lemon_ecs::component::ComponentHooks> {
+                let mut hooks = lemon_ecs::component::ComponentHooks::new::<Self>();
+                hooks #(.#names::<Self>(#paths))*;
+                Some(hooks)
+            }
+        }
+    };
+
     let gen = quote! {
         impl #impl_generics lemon_ecs::component::Component for #ident #ty_generics #where_clause {
             #[inline]
             fn as_empty_vec(&self) -> Box<dyn lemon_ecs::component::ComponentVec> {
                 Box::new(Vec::<#ident #ty_generics>::new())
             }
+
+            #declared_hooks
         }
     };
 
@@ -179,3 +234,288 @@ pub fn derive_bundle(input: TokenStream) -> TokenStream {
         Fields::Unit => panic!("Unit structs cannot derive Bundle"),
     }
 }
+
+// Replaces every occurrence of the lifetime `from` with `to`
+fn replace_lifetime(
+    tokens: proc_macro2::TokenStream,
+    from: &Ident,
+    to: &Lifetime,
+) -> proc_macro2::TokenStream {
+    use proc_macro2::{Group, TokenTree};
+
+    let mut output = proc_macro2::TokenStream::new();
+    let mut tokens = tokens.into_iter().peekable();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == '\'' => match tokens.peek() {
+                Some(TokenTree::Ident(ident)) if ident == from => {
+                    tokens.next();
+                    to.to_tokens(&mut output);
+                }
+                _ => output.extend([TokenTree::Punct(punct)]),
+            },
+            TokenTree::Group(group) => {
+                let mut replaced =
+                    Group::new(group.delimiter(), replace_lifetime(group.stream(), from, to));
+
+                replaced.set_span(group.span());
+                output.extend([TokenTree::Group(replaced)]);
+            }
+            token => output.extend([token]),
+        }
+    }
+
+    output
+}
+
+// The fields of a struct, as members which can be used to access or initialize them
+fn struct_fields(data: Data, derive: &str) -> Result<Vec<(Member, Type)>> {
+    let fields = match data {
+        Data::Struct(DataStruct { fields, .. }) => fields,
+        _ => {
+            return Err(Error::new(
+                proc_macro2::Span::call_site(),
+                format!("Only structs can derive {}", derive),
+            ))
+        }
+    };
+
+    Ok(fields
+        .into_iter()
+        .enumerate()
+        .map(|(idx, field)| {
+            let member = match field.ident {
+                Some(ident) => Member::Named(ident),
+                None => Member::Unnamed(Index::from(idx)),
+            };
+
+            (member, field.ty)
+        })
+        .collect())
+}
+
+fn impl_query_data(input: DeriveInput) -> Result<proc_macro2::TokenStream> {
+    let ident = input.ident;
+
+    if input.generics.type_params().next().is_some()
+        || input.generics.const_params().next().is_some()
+        || input.generics.lifetimes().count() > 1
+    {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "QueryData structs can only have a single lifetime parameter",
+        ));
+    }
+
+    let lifetime = input
+        .generics
+        .lifetimes()
+        .next()
+        .map(|def| def.lifetime.ident.clone());
+
+    let fields = struct_fields(input.data, "QueryData")?;
+
+    let static_lifetime = Lifetime::new("'static", proc_macro2::Span::call_site());
+    let world_lifetime = Lifetime::new("'__world", proc_macro2::Span::call_site());
+
+    // The struct and its field types with the given lifetime
+    let with_lifetime = |tokens: proc_macro2::TokenStream, to: &Lifetime| match &lifetime {
+        Some(from) => replace_lifetime(tokens, from, to),
+        None => tokens,
+    };
+
+    let static_ty = match &lifetime {
+        Some(_) => quote! { #ident<'static> },
+        None => quote! { #ident },
+    };
+
+    let world_ty = match &lifetime {
+        Some(_) => quote! { #ident<'__world> },
+        None => quote! { #ident },
+    };
+
+    let members: Vec<&Member> = fields.iter().map(|(member, _)| member).collect();
+
+    let chunks: Vec<Ident> = (0..fields.len())
+        .map(|idx| format_ident!("__chunk{}", idx))
+        .collect();
+
+    let world_fields: Vec<proc_macro2::TokenStream> = fields
+        .iter()
+        .map(|(_, ty)| with_lifetime(ty.to_token_stream(), &world_lifetime))
+        .collect();
+
+    let retrievers: Vec<proc_macro2::TokenStream> = fields
+        .iter()
+        .map(|(_, ty)| {
+            let ty = with_lifetime(ty.to_token_stream(), &static_lifetime);
+            quote! { <#ty as lemon_ecs::query::QueryField<'static>>::Retriever }
+        })
+        .collect();
+
+    // Only implemented if every field is read-only, which is decided by the field types themselves,
+    // so type aliases and other types hiding a mutable reference are handled correctly.
+    // The bounds are higher-ranked, since bounds without generic parameters must always hold.
+    let read_only_impls = quote! {
+        impl lemon_ecs::query::QueryRetriever for #static_ty
+        where
+            #(for<'__world> #world_fields: lemon_ecs::query::ReadOnlyQueryField<'__world>,)*
+        {
+            type Output<'__world> = #world_ty;
+
+            #[inline]
+            fn retrieve<'__world>(
+                entity: &lemon_ecs::entities::Entity<'__world>,
+            ) -> Self::Output<'__world> {
+                <#world_ty as lemon_ecs::query::ReadOnlyQueryField<'__world>>::retrieve(entity)
+            }
+        }
+
+        impl lemon_ecs::query::ReadOnlyChunk for #static_ty
+        where
+            #(for<'__world> #world_fields: lemon_ecs::query::ReadOnlyQueryField<'__world>,)*
+        {}
+
+        impl<'__world> lemon_ecs::query::ReadOnlyQueryField<'__world> for #world_ty
+        where
+            #(#world_fields: lemon_ecs::query::ReadOnlyQueryField<'__world>,)*
+        {
+            fn retrieve(entity: &lemon_ecs::entities::Entity<'__world>) -> Self {
+                #ident {
+                    #(#members: <#world_fields as lemon_ecs::query::ReadOnlyQueryField<'__world>>::retrieve(entity),)*
+                }
+            }
+        }
+    };
+
+    Ok(quote! {
+        impl lemon_ecs::query::QuerySelector for #static_ty {
+            #[inline]
+            fn filter(archetype: &lemon_ecs::entities::Archetype) -> bool {
+                true #(&& <#retrievers as lemon_ecs::query::QuerySelector>::filter(archetype))*
+            }
+        }
+
+        impl lemon_ecs::query::ChunkRetriever for #static_ty {
+            type Chunk<'__world> = (#(<#retrievers as lemon_ecs::query::ChunkRetriever>::Chunk<'__world>,)*);
+            type Item<'__world> = #world_ty;
+
+            fn fetch_chunk<'__world>(
+                columns: &mut lemon_ecs::entities::Columns<'__world>,
+            ) -> Self::Chunk<'__world> {
+                (#(<#retrievers as lemon_ecs::query::ChunkRetriever>::fetch_chunk(columns),)*)
+            }
+
+            fn chunk_item<'__world>(chunk: Self::Chunk<'__world>, idx: usize) -> Self::Item<'__world> {
+                let (#(#chunks,)*) = chunk;
+
+                #ident {
+                    #(#members: <#world_fields as lemon_ecs::query::QueryField<'__world>>::from_item(
+                        <#retrievers as lemon_ecs::query::ChunkRetriever>::chunk_item(#chunks, idx),
+                    ),)*
+                }
+            }
+
+            fn split_first<'__world>(
+                chunk: Self::Chunk<'__world>,
+            ) -> (Self::Item<'__world>, Self::Chunk<'__world>) {
+                let (#(#chunks,)*) = chunk;
+                #(let #chunks = <#retrievers as lemon_ecs::query::ChunkRetriever>::split_first(#chunks);)*
+
+                let item = #ident {
+                    #(#members: <#world_fields as lemon_ecs::query::QueryField<'__world>>::from_item(#chunks.0),)*
+                };
+
+                (item, (#(#chunks.1,)*))
+            }
+        }
+
+        impl<'__world> lemon_ecs::query::QueryField<'__world> for #world_ty {
+            type Retriever = #static_ty;
+
+            #[inline]
+            fn from_item(
+                item: <Self::Retriever as lemon_ecs::query::ChunkRetriever>::Item<'__world>,
+            ) -> Self {
+                item
+            }
+        }
+
+        #read_only_impls
+    })
+}
+
+/// Derives the query traits for a struct of components, such as `&'w T`, `&'w mut T`, `Option<&'w T>` or `EntityId`.
+/// Structs without mutable references can be used with `World::query`, others with `World::query_mut` or `QueryState`.
+#[proc_macro_derive(QueryData)]
+pub fn derive_query_data(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    impl_query_data(input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+fn impl_query_filter(input: DeriveInput) -> Result<proc_macro2::TokenStream> {
+    let ident = input.ident;
+
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "QueryFilter structs cannot have generic parameters",
+        ));
+    }
+
+    let types: Vec<Type> = struct_fields(input.data, "QueryFilter")?
+        .into_iter()
+        .map(|(_, ty)| ty)
+        .collect();
+
+    Ok(quote! {
+        impl lemon_ecs::query::QuerySelector for #ident {
+            #[inline]
+            fn filter(archetype: &lemon_ecs::entities::Archetype) -> bool {
+                true #(&& <#types as lemon_ecs::query::QuerySelector>::filter(archetype))*
+            }
+        }
+
+        impl lemon_ecs::query::QueryRetriever for #ident {
+            type Output<'__world> = ();
+
+            #[inline]
+            fn retrieve<'__world>(_entity: &lemon_ecs::entities::Entity<'__world>) -> Self::Output<'__world> {}
+        }
+
+        impl lemon_ecs::query::ChunkRetriever for #ident {
+            type Chunk<'__world> = ();
+            type Item<'__world> = ();
+
+            #[inline]
+            fn fetch_chunk<'__world>(_columns: &mut lemon_ecs::entities::Columns<'__world>) -> Self::Chunk<'__world> {}
+
+            #[inline]
+            fn chunk_item<'__world>(_chunk: Self::Chunk<'__world>, _idx: usize) -> Self::Item<'__world> {}
+
+            #[inline]
+            fn split_first<'__world>(
+                _chunk: Self::Chunk<'__world>,
+            ) -> (Self::Item<'__world>, Self::Chunk<'__world>) {
+                ((), ())
+            }
+        }
+
+        impl lemon_ecs::query::ReadOnlyChunk for #ident {}
+    })
+}
+
+/// Derives the query traits for a struct of selectors such as `With<T>` and `Without<T>`.
+/// The struct matches entities matching every field, and retrieves nothing.
+#[proc_macro_derive(QueryFilter)]
+pub fn derive_query_filter(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    impl_query_filter(input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
@@ -6,20 +6,50 @@ use crate::{
     world::ComponentChange,
 };
 
-pub struct ExistingEntityBuffer(EntityId, HashMap<TypeId, ComponentChange>);
+/// Changes to a single existing entity, applied in the order they were recorded.
+pub struct ExistingEntityBuffer {
+    id: EntityId,
+    changes: Vec<ComponentChange>,
+    coalescing: bool,
+}
 
 impl ExistingEntityBuffer {
     pub fn new(id: EntityId) -> Self {
-        Self(id, HashMap::new())
+        Self {
+            id,
+            changes: Vec::new(),
+            coalescing: false,
+        }
+    }
+
+    /// Creates a buffer which only keeps the last change of each component type.
+    pub fn coalescing(id: EntityId) -> Self {
+        Self {
+            coalescing: true,
+            ..Self::new(id)
+        }
     }
 
     pub fn id(&self) -> EntityId {
-        self.0
+        self.id
+    }
+
+    pub(crate) fn push_change(&mut self, change: ComponentChange) {
+        self.push(change_type_id(&change), change);
+    }
+
+    fn push(&mut self, type_id: TypeId, change: ComponentChange) {
+        if self.coalescing {
+            self.changes
+                .retain(|change| change_type_id(change) != type_id);
+        }
+
+        self.changes.push(change);
     }
 
     pub fn insert(&mut self, components: impl Bundle) -> &mut Self {
         for component in components.components() {
-            self.1.insert(
+            self.push(
                 (*component).as_any().type_id(),
                 ComponentChange::Insert(component),
             );
@@ -30,28 +60,81 @@ impl ExistingEntityBuffer {
 
     pub fn remove<T: TypeBundle>(&mut self) -> &mut Self {
         for type_id in T::type_ids() {
-            self.1.insert(type_id, ComponentChange::Remove(type_id));
+            self.push(type_id, ComponentChange::Remove(type_id));
         }
 
         self
     }
 
+    pub(crate) fn changes(&self) -> &[ComponentChange] {
+        &self.changes
+    }
+
+    /// Drops every recorded change.
+    pub(crate) fn clear(&mut self) {
+        self.changes.clear();
+    }
+
     pub fn into_changes(self) -> Vec<ComponentChange> {
-        self.1.into_values().collect()
+        self.changes
+    }
+}
+
+fn change_type_id(change: &ComponentChange) -> TypeId {
+    match change {
+        ComponentChange::Insert(component) => (**component).as_any().type_id(),
+        ComponentChange::Remove(type_id) => *type_id,
     }
 }
 
-#[repr(transparent)]
-pub struct SpawnedEntityBuffer(HashMap<TypeId, Box<dyn Component>>);
+pub struct SpawnedEntityBuffer {
+    id: Option<EntityId>,
+    reserved: bool,
+    components: HashMap<TypeId, Box<dyn Component>>,
+}
 
 impl SpawnedEntityBuffer {
     pub fn new() -> Self {
-        Self(HashMap::new())
+        Self {
+            id: None,
+            reserved: false,
+            components: HashMap::new(),
+        }
+    }
+
+    /// Creates a buffer for an ID reserved by the recording `WorldBuffer`, released if it's never spawned.
+    pub(crate) fn reserved(id: EntityId) -> Self {
+        Self {
+            id: Some(id),
+            reserved: true,
+            components: HashMap::new(),
+        }
+    }
+
+    /// Creates a buffer for an ID chosen elsewhere, such as one read from a command log.
+    pub(crate) fn with_id(id: EntityId) -> Self {
+        Self {
+            id: Some(id),
+            reserved: false,
+            components: HashMap::new(),
+        }
+    }
+
+    /// Returns the ID reserved for the entity, if it was reserved by the recording buffer.
+    #[inline]
+    pub(crate) fn reserved_id(&self) -> Option<EntityId> {
+        self.id.filter(|_| self.reserved)
+    }
+
+    /// Returns the ID the entity will be spawned with, if the buffer was created by a `World`.
+    pub fn id(&self) -> Option<EntityId> {
+        self.id
     }
 
     pub fn insert(&mut self, components: impl Bundle) -> &mut Self {
         for component in components.components() {
-            self.0.insert((*component).as_any().type_id(), component);
+            self.components
+                .insert((*component).as_any().type_id(), component);
         }
 
         self
@@ -59,14 +142,18 @@ impl SpawnedEntityBuffer {
 
     pub fn remove<T: TypeBundle>(&mut self) -> &mut Self {
         for type_id in T::type_ids() {
-            self.0.remove(&type_id);
+            self.components.remove(&type_id);
         }
 
         self
     }
 
+    pub(crate) fn components(&self) -> impl Iterator<Item = &dyn Component> {
+        self.components.values().map(|component| component.as_ref())
+    }
+
     pub fn into_components(self) -> Vec<Box<dyn Component>> {
-        self.0.into_values().collect()
+        self.components.into_values().collect()
     }
 }
 
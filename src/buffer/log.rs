@@ -0,0 +1,127 @@
+use crate::{
+    codec::{write_varint, CodecError, Decode, Encode, Reader},
+    component::ComponentRegistry,
+    entities::EntityId,
+    world::ComponentChange,
+};
+
+use super::{Command, ExistingEntityBuffer, SpawnedEntityBuffer, WorldBuffer};
+
+const SPAWN: u8 = 0;
+const DESPAWN: u8 = 1;
+const MODIFY: u8 = 2;
+
+const INSERT: u8 = 0;
+const REMOVE: u8 = 1;
+
+impl WorldBuffer {
+    /// Encodes the commands into a compact binary log, which can be decoded with `decode`.
+    /// Fails if a command uses a component which isn't registered, or a resource.
+    pub fn encode(&self, registry: &ComponentRegistry) -> Result<Vec<u8>, CodecError> {
+        let mut bytes = Vec::new();
+        write_varint(self.commands.len() as u64, &mut bytes);
+
+        for command in &self.commands {
+            match command {
+                Command::Spawn(buffer) => {
+                    bytes.push(SPAWN);
+                    buffer.id().encode(&mut bytes);
+
+                    // Sorted by index, so the same commands always produce the same bytes.
+                    let mut components = buffer
+                        .components()
+                        .map(|component| {
+                            Ok((registry.index_of(component.as_any().type_id())?, component))
+                        })
+                        .collect::<Result<Vec<_>, CodecError>>()?;
+                    components.sort_by_key(|(idx, _)| *idx);
+
+                    write_varint(components.len() as u64, &mut bytes);
+                    for (_, component) in components {
+                        registry.encode(component, &mut bytes)?;
+                    }
+                }
+                Command::Despawn(id) => {
+                    bytes.push(DESPAWN);
+                    id.encode(&mut bytes);
+                }
+                Command::Modify(buffer) => {
+                    bytes.push(MODIFY);
+                    buffer.id().encode(&mut bytes);
+                    write_varint(buffer.changes().len() as u64, &mut bytes);
+
+                    for change in buffer.changes() {
+                        match change {
+                            ComponentChange::Insert(component) => {
+                                bytes.push(INSERT);
+                                registry.encode(component.as_ref(), &mut bytes)?;
+                            }
+                            ComponentChange::Remove(type_id) => {
+                                bytes.push(REMOVE);
+                                write_varint(registry.index_of(*type_id)? as u64, &mut bytes);
+                            }
+                        }
+                    }
+                }
+                Command::InsertResource(_)
+                | Command::RemoveResource(_)
+                | Command::ModifyResource(_) => return Err(CodecError::Resource),
+            }
+        }
+
+        Ok(bytes)
+    }
+
+    /// Decodes a log produced by `encode` with the same registered components.
+    /// Entities spawned with an ID are spawned with exactly that ID when the buffer is applied.
+    pub fn decode(bytes: &[u8], registry: &ComponentRegistry) -> Result<Self, CodecError> {
+        let mut reader = Reader::new(bytes);
+        let mut buffer = WorldBuffer::new();
+
+        for _ in 0..reader.read_len()? {
+            let command = match reader.read_u8()? {
+                SPAWN => {
+                    let mut spawned = match Option::<EntityId>::decode(&mut reader)? {
+                        Some(id) => SpawnedEntityBuffer::with_id(id),
+                        None => SpawnedEntityBuffer::new(),
+                    };
+
+                    let components = (0..reader.read_len()?)
+                        .map(|_| registry.decode(&mut reader))
+                        .collect::<Result<Vec<_>, CodecError>>()?;
+
+                    spawned.insert(components);
+
+                    Command::Spawn(spawned)
+                }
+                DESPAWN => Command::Despawn(EntityId::decode(&mut reader)?),
+                MODIFY => {
+                    let mut modified = ExistingEntityBuffer::new(EntityId::decode(&mut reader)?);
+
+                    for _ in 0..reader.read_len()? {
+                        let change = match reader.read_u8()? {
+                            INSERT => ComponentChange::Insert(registry.decode(&mut reader)?),
+                            REMOVE => {
+                                ComponentChange::Remove(registry.type_id_of(reader.read_varint()?)?)
+                            }
+                            _ => return Err(CodecError::Invalid("component change")),
+                        };
+
+                        modified.push_change(change);
+                    }
+
+                    Command::Modify(modified)
+                }
+                _ => return Err(CodecError::Invalid("command")),
+            };
+
+            buffer.commands.push(command);
+        }
+
+        if !reader.is_empty() {
+            return Err(CodecError::Invalid("trailing data"));
+        }
+
+        Ok(buffer)
+    }
+}
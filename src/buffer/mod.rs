@@ -1,24 +1,50 @@
-use std::any::Any;
+use std::any::{Any, TypeId};
 
 use crate::{
     component::{Bundle, TypeBundle},
-    entities::EntityId,
+    entities::{EntityId, IdReserver},
     sparse_set::SparseSet,
-    world::World,
+    world::{SpawnError, World},
 };
 
 mod entity;
+mod log;
+mod parallel;
 
 pub use entity::*;
+pub use parallel::*;
+
+enum Command {
+    Spawn(SpawnedEntityBuffer),
+    Despawn(EntityId),
+    Modify(ExistingEntityBuffer),
+    InsertResource(Box<dyn Any + Send + Sync>),
+    RemoveResource(TypeId),
+    ModifyResource(ResourceFn),
+}
+
+/// A function recorded by `modify_resource`, which only needs to be `Send`.
+struct ResourceFn(Box<dyn FnOnce(&mut World) + Send>);
+
+// The function is only ever taken by value to be called, never through a shared reference.
+unsafe impl Sync for ResourceFn {}
 
 /// Used to store changes to the `World` when they can't be applied immediately.
 /// For example, iterating through a `Query` and modifying the `World` at the same time is not allowed.
+///
+/// Commands are applied in the order they were recorded, so inserting, removing and inserting
+/// a component again behaves exactly like doing it on the `World` directly.
+/// In coalescing mode, all changes to an entity are merged into its first queued modification,
+/// where only the last change of each component type is kept, and despawning an entity drops its queued changes.
+///
+/// Buffers created with `World::buffer` reserve entity IDs, so spawned entities can be referenced right away.
+/// The IDs of spawns which are never applied are given back when the buffer is dropped.
 #[derive(Default)]
 pub struct WorldBuffer {
-    existing_entities: SparseSet<ExistingEntityBuffer>,
-    spawned_entities: Vec<SpawnedEntityBuffer>,
-    despawned_entities: Vec<EntityId>,
-    resources: Vec<Box<dyn Any>>,
+    entities: Option<IdReserver>,
+    commands: Vec<Command>,
+    coalescing: bool,
+    coalesced: SparseSet<usize>,
 }
 
 impl WorldBuffer {
@@ -26,56 +52,201 @@ impl WorldBuffer {
         Self::default()
     }
 
+    pub(crate) fn with_ids(entities: IdReserver) -> Self {
+        Self {
+            entities: Some(entities),
+            commands: Vec::new(),
+            coalescing: false,
+            coalesced: SparseSet::new(),
+        }
+    }
+
+    /// Takes the recorded changes, leaving an empty buffer with the same settings.
+    pub(crate) fn take(&mut self) -> Self {
+        let empty = Self {
+            entities: self.entities.clone(),
+            commands: Vec::new(),
+            coalescing: self.coalescing,
+            coalesced: SparseSet::new(),
+        };
+
+        std::mem::replace(self, empty)
+    }
+
+    /// Enables or disables coalescing for the commands recorded from now on.
+    pub fn set_coalescing(&mut self, coalescing: bool) {
+        self.coalescing = coalescing;
+        self.coalesced = SparseSet::new();
+    }
+
+    #[inline]
+    pub fn is_coalescing(&self) -> bool {
+        self.coalescing
+    }
+
     pub fn spawn(&mut self, bundle: impl Bundle) -> &mut SpawnedEntityBuffer {
-        let mut buffer = SpawnedEntityBuffer::new();
+        let mut buffer = match &self.entities {
+            Some(entities) => SpawnedEntityBuffer::reserved(entities.reserve()),
+            None => SpawnedEntityBuffer::new(),
+        };
+
         buffer.insert(bundle);
+        self.commands.push(Command::Spawn(buffer));
 
-        self.spawned_entities.push(buffer);
-        self.spawned_entities.last_mut().unwrap()
+        match self.commands.last_mut() {
+            Some(Command::Spawn(buffer)) => buffer,
+            _ => unreachable!(),
+        }
     }
 
     pub fn despawn(&mut self, id: EntityId) {
-        self.despawned_entities.push(id);
-        self.existing_entities.remove(id);
+        if let Some(idx) = self.coalesced.remove(id) {
+            if let Command::Modify(buffer) = &mut self.commands[idx] {
+                buffer.clear();
+            }
+        }
+
+        self.commands.push(Command::Despawn(id));
     }
 
-    pub fn insert(&mut self, id: EntityId, components: impl Bundle) -> &mut ExistingEntityBuffer {
-        let buffer = self
-            .existing_entities
-            .get_or_insert_with(id, || ExistingEntityBuffer::new(id));
+    fn entity_buffer(&mut self, id: EntityId) -> &mut ExistingEntityBuffer {
+        let idx = if self.coalescing {
+            let commands = &mut self.commands;
+
+            *self.coalesced.get_or_insert_with(id, || {
+                commands.push(Command::Modify(ExistingEntityBuffer::coalescing(id)));
+                commands.len() - 1
+            })
+        } else {
+            self.commands
+                .push(Command::Modify(ExistingEntityBuffer::new(id)));
+            self.commands.len() - 1
+        };
+
+        match &mut self.commands[idx] {
+            Command::Modify(buffer) => buffer,
+            _ => unreachable!(),
+        }
+    }
 
+    pub fn insert(&mut self, id: EntityId, components: impl Bundle) -> &mut ExistingEntityBuffer {
+        let buffer = self.entity_buffer(id);
         buffer.insert(components);
         buffer
     }
 
     pub fn remove<T: TypeBundle>(&mut self, id: EntityId) -> &mut ExistingEntityBuffer {
-        let buffer = self
-            .existing_entities
-            .get_or_insert_with(id, || ExistingEntityBuffer::new(id));
-
+        let buffer = self.entity_buffer(id);
         buffer.remove::<T>();
         buffer
     }
 
-    pub fn insert_resource(&mut self, resource: impl Any + 'static) {
-        self.resources.push(Box::new(resource));
+    /// Moves the commands of another buffer after the ones recorded in this buffer.
+    pub fn append(&mut self, mut other: WorldBuffer) {
+        if let (Some(entities), Some(other)) = (&self.entities, &other.entities) {
+            assert!(
+                entities.is_shared_with(other),
+                "WorldBuffers created by different Worlds cannot be merged."
+            );
+        }
+
+        if self.entities.is_none() {
+            self.entities = other.entities.take();
+        }
+
+        self.commands.append(&mut other.commands);
+        self.coalesced = SparseSet::new();
+    }
+
+    /// Returns true if no changes have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
     }
 
+    pub fn insert_resource(&mut self, resource: impl Any + Send + Sync + 'static) {
+        self.commands
+            .push(Command::InsertResource(Box::new(resource)));
+    }
+
+    pub fn remove_resource<T: 'static>(&mut self) {
+        self.commands
+            .push(Command::RemoveResource(TypeId::of::<T>()));
+    }
+
+    /// Records a function modifying a resource, which is skipped if the resource doesn't exist when applied.
+    pub fn modify_resource<T: 'static>(&mut self, f: impl FnOnce(&mut T) + Send + 'static) {
+        self.commands
+            .push(Command::ModifyResource(ResourceFn(Box::new(
+                |world: &mut World| {
+                    if let Some(resource) = world.get_resource_mut::<T>() {
+                        f(resource);
+                    }
+                },
+            ))));
+    }
+
+    /// Applies the commands to the world in the order they were recorded,
+    /// panicking if the buffer was created by another `World`, or if an entity can't be spawned with its ID.
     pub fn apply_world(self, world: &mut World) {
-        for buffer in self.spawned_entities {
-            world.spawn(buffer.into_components());
+        if let Err(error) = self.try_apply_world(world) {
+            panic!("WorldBuffer could not be applied: {}.", error);
         }
+    }
 
-        for id in self.despawned_entities {
-            world.despawn(id);
+    /// Applies the commands to the world in the order they were recorded,
+    /// stopping at the first entity which can't be spawned with its ID, such as one read from an untrusted log.
+    /// The commands recorded before the failing spawn remain applied.
+    /// Panics if the buffer was created by another `World`.
+    pub fn try_apply_world(mut self, world: &mut World) -> Result<(), SpawnError> {
+        if let Some(entities) = &self.entities {
+            assert!(
+                entities.is_shared_with(world.id_reserver()),
+                "WorldBuffer cannot be applied to a World other than the one which created it."
+            );
         }
 
-        for (id, buffer) in self.existing_entities.into_iter() {
-            world.modify(id, buffer.into_changes());
+        let mut commands = std::mem::take(&mut self.commands).into_iter();
+
+        while let Some(command) = commands.next() {
+            match command {
+                Command::Spawn(buffer) => {
+                    let result = match (buffer.reserved_id(), buffer.id()) {
+                        (Some(id), _) => world.try_spawn_reserved(id, buffer.into_components()),
+                        (None, Some(id)) => world.spawn_at(id, buffer.into_components()),
+                        (None, None) => {
+                            world.spawn(buffer.into_components());
+                            Ok(())
+                        }
+                    };
+
+                    if let Err(error) = result {
+                        self.commands.extend(commands);
+                        return Err(error);
+                    }
+                }
+                Command::Despawn(id) => world.despawn(id),
+                Command::Modify(buffer) => world.modify(buffer.id(), buffer.into_changes()),
+                Command::InsertResource(resource) => world.insert_resource_boxed(resource),
+                Command::RemoveResource(type_id) => world.remove_resource_by_id(type_id),
+                Command::ModifyResource(ResourceFn(f)) => f(world),
+            }
         }
 
-        for resource in self.resources {
-            world.insert_resource_boxed(resource);
+        Ok(())
+    }
+}
+
+impl Drop for WorldBuffer {
+    fn drop(&mut self) {
+        if let Some(entities) = &self.entities {
+            for command in &self.commands {
+                if let Some(id) = match command {
+                    Command::Spawn(buffer) => buffer.reserved_id(),
+                    _ => None,
+                } {
+                    entities.release(id);
+                }
+            }
         }
     }
 }
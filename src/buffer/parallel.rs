@@ -0,0 +1,106 @@
+use std::{
+    collections::BTreeMap,
+    ops::{Deref, DerefMut},
+    sync::Mutex,
+};
+
+use crate::{entities::IdReserver, world::World};
+
+use super::WorldBuffer;
+
+/// Collects `WorldBuffer`s recorded concurrently by several threads.
+/// Each thread records into its own `LocalBuffer` without locking, which is submitted when dropped.
+/// The buffers are merged by system order, then by thread index, so the result doesn't depend on scheduling.
+#[derive(Default)]
+pub struct ParallelBuffer {
+    entities: Option<IdReserver>,
+    buffers: Mutex<BTreeMap<(usize, usize), WorldBuffer>>,
+}
+
+impl ParallelBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn with_ids(entities: IdReserver) -> Self {
+        Self {
+            entities: Some(entities),
+            buffers: Mutex::default(),
+        }
+    }
+
+    /// Returns an empty buffer for the given system order and thread index.
+    /// Commands recorded by several local buffers with the same keys are merged in the order they are dropped.
+    pub fn local(&self, order: usize, thread: usize) -> LocalBuffer<'_> {
+        let buffer = match &self.entities {
+            Some(entities) => WorldBuffer::with_ids(entities.clone()),
+            None => WorldBuffer::new(),
+        };
+
+        LocalBuffer {
+            parent: self,
+            key: (order, thread),
+            buffer: Some(buffer),
+        }
+    }
+
+    fn submit(&self, key: (usize, usize), buffer: WorldBuffer) {
+        let mut buffers = self.buffers.lock().unwrap();
+
+        match buffers.get_mut(&key) {
+            Some(existing) => existing.append(buffer),
+            None => {
+                buffers.insert(key, buffer);
+            }
+        }
+    }
+
+    /// Merges the submitted buffers into a single one.
+    pub fn into_buffer(self) -> WorldBuffer {
+        let buffers = self.buffers.into_inner().unwrap();
+        let mut merged = match self.entities {
+            Some(entities) => WorldBuffer::with_ids(entities),
+            None => WorldBuffer::new(),
+        };
+
+        for buffer in buffers.into_values() {
+            merged.append(buffer);
+        }
+
+        merged
+    }
+
+    #[inline]
+    pub fn apply_world(self, world: &mut World) {
+        self.into_buffer().apply_world(world);
+    }
+}
+
+/// A `WorldBuffer` owned by a single thread, submitted to its `ParallelBuffer` when dropped.
+pub struct LocalBuffer<'a> {
+    parent: &'a ParallelBuffer,
+    key: (usize, usize),
+    buffer: Option<WorldBuffer>,
+}
+
+impl Deref for LocalBuffer<'_> {
+    type Target = WorldBuffer;
+
+    fn deref(&self) -> &Self::Target {
+        self.buffer.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for LocalBuffer<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.buffer.as_mut().unwrap()
+    }
+}
+
+impl Drop for LocalBuffer<'_> {
+    fn drop(&mut self) {
+        if let Some(buffer) = self.buffer.take() {
+            self.parent.submit(self.key, buffer);
+        }
+    }
+}
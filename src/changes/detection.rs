@@ -5,12 +5,24 @@ use crate::{component::Component, entities::EntityId};
 use super::ChangeRecord;
 
 #[derive(Default)]
-#[repr(transparent)]
 pub(crate) struct ChangeDetection {
     records: HashMap<TypeId, ChangeRecord>,
+    tick: u64,
 }
 
 impl ChangeDetection {
+    /// Returns the change tick stamped on changes marked from now on.
+    #[inline]
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Advances the change tick, returning the new value.
+    pub fn increment_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
     /// Returns a reference to the change record for the given component type.
     /// If the component type has not been registered, a new record is created.
     #[inline]
@@ -32,22 +44,28 @@ impl ChangeDetection {
 
     /// Marks the component as added for the given entity.
     pub fn mark_added(&mut self, id: EntityId, type_id: TypeId) {
+        let tick = self.tick;
+
         if let Some(record) = self.get_mut_record(type_id) {
-            record.mark_added(id);
+            record.mark_added(id, tick);
         }
     }
 
     /// Marks the component as removed for the given entity.
     pub fn mark_removed(&mut self, id: EntityId, component: Box<dyn Component>) {
+        let tick = self.tick;
+
         if let Some(record) = self.get_mut_record((*component).as_any().type_id()) {
-            record.mark_removed(id, component);
+            record.mark_removed(id, component, tick);
         }
     }
 
     /// Marks the component as changed for the given entity.
     pub fn mark_changed(&mut self, id: EntityId, component: Box<dyn Component>) {
+        let tick = self.tick;
+
         if let Some(record) = self.get_mut_record((*component).as_any().type_id()) {
-            record.mark_changed(id, component);
+            record.mark_changed(id, component, tick);
         }
     }
 
@@ -1,4 +1,9 @@
-use crate::{component::Component, entities::EntityId, sparse_set, world::World};
+use crate::{
+    component::Component,
+    entities::EntityId,
+    sparse_set::{self, SparseSet},
+    world::World,
+};
 
 use super::{ChangeRecord, ChangeStatus};
 
@@ -10,6 +15,7 @@ enum ComponentSnapshot<'world, T: Component> {
 
 pub struct EntitySnapshot<'world, T: Component> {
     id: EntityId,
+    tick: u64,
     component: ComponentSnapshot<'world, T>,
 }
 
@@ -34,6 +40,13 @@ impl<'world, T: Component> EntitySnapshot<'world, T> {
     pub fn id(&self) -> EntityId {
         self.id
     }
+
+    /// Returns the change tick of the last change to the component,
+    /// which can be compared with `SystemTicks` to find changes made since a system last ran.
+    #[inline]
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
 }
 
 macro_rules! downcast_vec_as_some {
@@ -48,6 +61,7 @@ macro_rules! downcast_vec_as_some {
 pub struct SnapshotIter<'world, T: Component> {
     world: &'world World,
     iter: sparse_set::IntoIter<ChangeStatus>,
+    ticks: SparseSet<u64>,
     removed: Vec<Option<T>>,
 }
 
@@ -56,6 +70,7 @@ impl<'world, T: Component> SnapshotIter<'world, T> {
         Self {
             world,
             iter: record.entities.into_iter(),
+            ticks: record.ticks,
             removed: downcast_vec_as_some!(record.removed),
         }
     }
@@ -77,7 +92,11 @@ impl<'world, T: Component> Iterator for SnapshotIter<'world, T> {
                 ),
             };
 
-            EntitySnapshot { id, component }
+            EntitySnapshot {
+                id,
+                tick: self.ticks.get(id).copied().unwrap_or_default(),
+                component,
+            }
         })
     }
 }
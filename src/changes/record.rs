@@ -10,10 +10,11 @@ pub(crate) enum ChangeStatus {
     Removed(usize),
 }
 
-/// Records changes to a specific component type.
+/// Records changes to a specific component type, along with the change tick of the last change to each entity.
 /// Although it is untyped, it is only usable for a single component type.
 pub(crate) struct ChangeRecord {
     pub entities: SparseSet<ChangeStatus>,
+    pub ticks: SparseSet<u64>,
     pub removed: Box<dyn ComponentVec>,
 }
 
@@ -28,19 +29,26 @@ impl ChangeRecord {
     pub fn from_type<T: Component>() -> Self {
         Self {
             entities: SparseSet::new(),
+            ticks: SparseSet::new(),
             removed: Box::<Vec<T>>::default(),
         }
     }
 
-    pub fn from_ids<T: Component>(ids: impl Iterator<Item = EntityId>) -> Self {
+    pub fn from_ids<T: Component>(ids: impl Iterator<Item = EntityId>, tick: u64) -> Self {
+        let entities: SparseSet<_> = ids.map(|id| (id, ChangeStatus::Added)).collect();
+        let ticks = entities.ids().iter().map(|id| (*id, tick)).collect();
+
         Self {
-            entities: SparseSet::from_iter(ids.map(|id| (id, ChangeStatus::Added))),
+            entities,
+            ticks,
             removed: Box::<Vec<T>>::default(),
         }
     }
 
     /// Marks the component as added for the given entity.
-    pub fn mark_added(&mut self, id: EntityId) {
+    pub fn mark_added(&mut self, id: EntityId, tick: u64) {
+        self.ticks.insert(id, tick);
+
         match self.entities.get_mut(id) {
             Some(status) => match status {
                 ChangeStatus::Removed(id) => *status = ChangeStatus::Modified(*id),
@@ -53,11 +61,14 @@ impl ChangeRecord {
     }
 
     /// Marks the component as removed for the given entity.
-    pub fn mark_removed(&mut self, id: EntityId, removed: Box<dyn Component>) {
+    pub fn mark_removed(&mut self, id: EntityId, removed: Box<dyn Component>, tick: u64) {
+        self.ticks.insert(id, tick);
+
         match self.entities.get_mut(id) {
             Some(status) => match status {
                 ChangeStatus::Added => {
                     self.entities.remove(id);
+                    self.ticks.remove(id);
                 }
                 ChangeStatus::Modified(id) => {
                     self.removed.replace(*id, removed);
@@ -75,7 +86,9 @@ impl ChangeRecord {
     }
 
     /// Marks the component as changed for the given entity.
-    pub fn mark_changed(&mut self, id: EntityId, removed: Box<dyn Component>) {
+    pub fn mark_changed(&mut self, id: EntityId, removed: Box<dyn Component>, tick: u64) {
+        self.ticks.insert(id, tick);
+
         match self.entities.get_mut(id) {
             Some(status) => match status {
                 ChangeStatus::Added | ChangeStatus::Modified(_) => {}
@@ -0,0 +1,309 @@
+use std::{any::TypeId, fmt::Display};
+
+use lemon_ecs_macros::all_tuples;
+
+use crate::entities::EntityId;
+
+/// Errors produced while encoding or decoding binary data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodecError {
+    /// The component type hasn't been registered in the `ComponentRegistry`.
+    UnregisteredComponent(TypeId),
+    /// The encoded component index doesn't match any registered component.
+    UnknownComponent(u64),
+    /// Resources can't be encoded, since they aren't registered.
+    Resource,
+    /// The data ended before a value was fully decoded.
+    UnexpectedEnd,
+    /// The data contains a value which can't be decoded.
+    Invalid(&'static str),
+}
+
+impl Display for CodecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CodecError::UnregisteredComponent(type_id) => {
+                write!(f, "component {:?} has not been registered", type_id)
+            }
+            CodecError::UnknownComponent(idx) => write!(f, "unknown component index {}", idx),
+            CodecError::Resource => write!(f, "resources cannot be encoded"),
+            CodecError::UnexpectedEnd => write!(f, "unexpected end of data"),
+            CodecError::Invalid(what) => write!(f, "invalid {}", what),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+/// Reads values from a slice of encoded bytes.
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], CodecError> {
+        if self.bytes.len() < len {
+            return Err(CodecError::UnexpectedEnd);
+        }
+
+        let (bytes, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(bytes)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, CodecError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    /// Reads an unsigned LEB128 integer.
+    pub fn read_varint(&mut self) -> Result<u64, CodecError> {
+        let mut value = 0u64;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(CodecError::Invalid("varint"))
+    }
+
+    pub fn read_len(&mut self) -> Result<usize, CodecError> {
+        usize::try_from(self.read_varint()?).map_err(|_| CodecError::Invalid("length"))
+    }
+}
+
+/// Writes an unsigned LEB128 integer.
+pub fn write_varint(mut value: u64, bytes: &mut Vec<u8>) {
+    while value >= 0x80 {
+        bytes.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+
+    bytes.push(value as u8);
+}
+
+/// Types which can be written to the compact binary format used by command logs.
+pub trait Encode {
+    fn encode(&self, bytes: &mut Vec<u8>);
+}
+
+/// Types which can be read back from the compact binary format used by command logs.
+pub trait Decode: Sized {
+    fn decode(reader: &mut Reader<'_>) -> Result<Self, CodecError>;
+}
+
+macro_rules! impl_codec_unsigned {
+    ($($t:ty),*) => {
+        $(
+            impl Encode for $t {
+                #[inline]
+                fn encode(&self, bytes: &mut Vec<u8>) {
+                    write_varint(*self as u64, bytes);
+                }
+            }
+
+            impl Decode for $t {
+                #[inline]
+                fn decode(reader: &mut Reader<'_>) -> Result<Self, CodecError> {
+                    <$t>::try_from(reader.read_varint()?)
+                        .map_err(|_| CodecError::Invalid(stringify!($t)))
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_codec_signed {
+    ($($t:ty),*) => {
+        $(
+            impl Encode for $t {
+                #[inline]
+                fn encode(&self, bytes: &mut Vec<u8>) {
+                    let value = *self as i64;
+                    write_varint(((value << 1) ^ (value >> 63)) as u64, bytes);
+                }
+            }
+
+            impl Decode for $t {
+                #[inline]
+                fn decode(reader: &mut Reader<'_>) -> Result<Self, CodecError> {
+                    let value = reader.read_varint()?;
+                    let value = ((value >> 1) as i64) ^ -((value & 1) as i64);
+
+                    <$t>::try_from(value).map_err(|_| CodecError::Invalid(stringify!($t)))
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_codec_float {
+    ($($t:ty),*) => {
+        $(
+            impl Encode for $t {
+                #[inline]
+                fn encode(&self, bytes: &mut Vec<u8>) {
+                    bytes.extend_from_slice(&self.to_le_bytes());
+                }
+            }
+
+            impl Decode for $t {
+                #[inline]
+                fn decode(reader: &mut Reader<'_>) -> Result<Self, CodecError> {
+                    let bytes = reader.read_bytes(std::mem::size_of::<$t>())?;
+                    Ok(<$t>::from_le_bytes(bytes.try_into().unwrap()))
+                }
+            }
+        )*
+    };
+}
+
+impl_codec_unsigned!(u16, u32, u64, usize);
+impl_codec_signed!(i8, i16, i32, i64, isize);
+impl_codec_float!(f32, f64);
+
+impl Encode for u8 {
+    #[inline]
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        bytes.push(*self);
+    }
+}
+
+impl Decode for u8 {
+    #[inline]
+    fn decode(reader: &mut Reader<'_>) -> Result<Self, CodecError> {
+        reader.read_u8()
+    }
+}
+
+impl Encode for bool {
+    #[inline]
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        bytes.push(*self as u8);
+    }
+}
+
+impl Decode for bool {
+    fn decode(reader: &mut Reader<'_>) -> Result<Self, CodecError> {
+        match reader.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(CodecError::Invalid("bool")),
+        }
+    }
+}
+
+impl Encode for str {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        write_varint(self.len() as u64, bytes);
+        bytes.extend_from_slice(self.as_bytes());
+    }
+}
+
+impl Encode for String {
+    #[inline]
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.as_str().encode(bytes);
+    }
+}
+
+impl Decode for String {
+    fn decode(reader: &mut Reader<'_>) -> Result<Self, CodecError> {
+        let len = reader.read_len()?;
+        let bytes = reader.read_bytes(len)?;
+
+        String::from_utf8(bytes.to_vec()).map_err(|_| CodecError::Invalid("string"))
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        match self {
+            Some(value) => {
+                bytes.push(1);
+                value.encode(bytes);
+            }
+            None => bytes.push(0),
+        }
+    }
+}
+
+impl<T: Decode> Decode for Option<T> {
+    fn decode(reader: &mut Reader<'_>) -> Result<Self, CodecError> {
+        match reader.read_u8()? {
+            0 => Ok(None),
+            1 => Ok(Some(T::decode(reader)?)),
+            _ => Err(CodecError::Invalid("option")),
+        }
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        write_varint(self.len() as u64, bytes);
+
+        for value in self {
+            value.encode(bytes);
+        }
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode(reader: &mut Reader<'_>) -> Result<Self, CodecError> {
+        let len = reader.read_len()?;
+        (0..len).map(|_| T::decode(reader)).collect()
+    }
+}
+
+impl Encode for EntityId {
+    #[inline]
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.id().encode(bytes);
+    }
+}
+
+impl Decode for EntityId {
+    /// Fails if the ID is larger than `EntityId::MAX`, since it could never be spawned.
+    #[inline]
+    fn decode(reader: &mut Reader<'_>) -> Result<Self, CodecError> {
+        match usize::decode(reader) {
+            Ok(id) if id <= EntityId::MAX => Ok(EntityId::new(id)),
+            Ok(_) | Err(CodecError::Invalid(_)) => Err(CodecError::Invalid("entity ID")),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+macro_rules! impl_tuple_codec {
+    ($($t:ident),*) => {
+        impl<$($t: Encode),*> Encode for ($($t,)*) {
+            #[allow(non_snake_case, unused_variables)]
+            fn encode(&self, bytes: &mut Vec<u8>) {
+                let ($($t,)*) = self;
+                $($t.encode(bytes);)*
+            }
+        }
+
+        impl<$($t: Decode),*> Decode for ($($t,)*) {
+            #[allow(unused_variables)]
+            fn decode(reader: &mut Reader<'_>) -> Result<Self, CodecError> {
+                Ok(($($t::decode(reader)?,)*))
+            }
+        }
+    };
+}
+
+all_tuples!(impl_tuple_codec, 0..16);
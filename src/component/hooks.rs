@@ -0,0 +1,178 @@
+use std::{any::TypeId, collections::HashMap};
+
+use crate::{buffer::WorldBuffer, entities::EntityId};
+
+use super::Component;
+
+type ErasedHook = Box<dyn Fn(EntityId, &dyn Component, &mut WorldBuffer) + Send + Sync>;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum HookKind {
+    Add,
+    Insert,
+    Replace,
+    Remove,
+}
+
+/// Functions run synchronously when a component of a single type is added, inserted, replaced or removed.
+/// Hooks receive the entity, the component value and a buffer applied once the triggering change is done.
+///
+/// - `on_add` runs when the entity didn't have the component before.
+/// - `on_insert` runs after `on_add`, and whenever the component is replaced with a new value.
+/// - `on_replace` runs with the old value when the component is replaced or removed.
+/// - `on_remove` runs after `on_replace` when the component is removed, including on despawn.
+pub struct ComponentHooks {
+    type_id: TypeId,
+    on_add: Option<ErasedHook>,
+    on_insert: Option<ErasedHook>,
+    on_replace: Option<ErasedHook>,
+    on_remove: Option<ErasedHook>,
+}
+
+macro_rules! impl_hook_setter {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        pub fn $name<T: Component>(
+            &mut self,
+            hook: impl Fn(EntityId, &T, &mut WorldBuffer) + Send + Sync + 'static,
+        ) -> &mut Self {
+            self.$name = Some(self.erase(hook));
+            self
+        }
+    };
+}
+
+impl ComponentHooks {
+    pub fn new<T: Component>() -> Self {
+        Self::from_type_id(TypeId::of::<T>())
+    }
+
+    fn from_type_id(type_id: TypeId) -> Self {
+        Self {
+            type_id,
+            on_add: None,
+            on_insert: None,
+            on_replace: None,
+            on_remove: None,
+        }
+    }
+
+    impl_hook_setter!(
+        /// Sets the hook run when the component is added to an entity which didn't have it.
+        on_add
+    );
+
+    impl_hook_setter!(
+        /// Sets the hook run whenever a new value of the component is stored on an entity.
+        on_insert
+    );
+
+    impl_hook_setter!(
+        /// Sets the hook run with the old value when the component is replaced or removed.
+        on_replace
+    );
+
+    impl_hook_setter!(
+        /// Sets the hook run when the component is removed from an entity.
+        on_remove
+    );
+
+    fn erase<T: Component>(
+        &self,
+        hook: impl Fn(EntityId, &T, &mut WorldBuffer) + Send + Sync + 'static,
+    ) -> ErasedHook {
+        assert!(
+            self.type_id == TypeId::of::<T>(),
+            "Hook for {} cannot be registered on the hooks of another component.",
+            std::any::type_name::<T>()
+        );
+
+        Box::new(move |id, component, buffer| {
+            hook(id, component.downcast_ref::<T>().unwrap(), buffer)
+        })
+    }
+
+    /// Fills in the hooks which haven't been set from the given hooks.
+    fn merge(&mut self, other: ComponentHooks) {
+        self.on_add = self.on_add.take().or(other.on_add);
+        self.on_insert = self.on_insert.take().or(other.on_insert);
+        self.on_replace = self.on_replace.take().or(other.on_replace);
+        self.on_remove = self.on_remove.take().or(other.on_remove);
+    }
+
+    fn get(&self, kind: HookKind) -> Option<&ErasedHook> {
+        match kind {
+            HookKind::Add => self.on_add.as_ref(),
+            HookKind::Insert => self.on_insert.as_ref(),
+            HookKind::Replace => self.on_replace.as_ref(),
+            HookKind::Remove => self.on_remove.as_ref(),
+        }
+    }
+}
+
+struct HookEntry {
+    hooks: ComponentHooks,
+    declared: bool,
+}
+
+/// Stores the hooks of every component type along with the buffer they write to.
+pub(crate) struct HookRegistry {
+    hooks: HashMap<TypeId, HookEntry>,
+    buffer: WorldBuffer,
+}
+
+impl HookRegistry {
+    pub fn new(buffer: WorldBuffer) -> Self {
+        Self {
+            hooks: HashMap::new(),
+            buffer,
+        }
+    }
+
+    pub fn hooks_mut<T: Component>(&mut self) -> &mut ComponentHooks {
+        &mut self
+            .hooks
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| HookEntry {
+                hooks: ComponentHooks::new::<T>(),
+                declared: false,
+            })
+            .hooks
+    }
+
+    /// Runs the hook of the given kind for the component, registering its declared hooks on first sight.
+    pub fn trigger(&mut self, kind: HookKind, id: EntityId, component: &dyn Component) {
+        let type_id = component.as_any().type_id();
+
+        let entry = self.hooks.entry(type_id).or_insert_with(|| HookEntry {
+            hooks: ComponentHooks::from_type_id(type_id),
+            declared: false,
+        });
+
+        if !entry.declared {
+            entry.declared = true;
+
+            if let Some(declared) = component.declared_hooks() {
+                entry.hooks.merge(declared);
+            }
+        }
+
+        if let Some(hook) = entry.hooks.get(kind) {
+            hook(id, component, &mut self.buffer);
+        }
+    }
+
+    #[inline]
+    pub fn buffer_mut(&mut self) -> &mut WorldBuffer {
+        &mut self.buffer
+    }
+
+    /// Takes the commands recorded by hooks, if there are any.
+    pub fn take_buffer(&mut self) -> Option<WorldBuffer> {
+        if self.buffer.is_empty() {
+            None
+        } else {
+            Some(self.buffer.take())
+        }
+    }
+}
@@ -1,13 +1,25 @@
 use crate::downcast::{impl_downcast, AsAny};
 
 mod bundle;
+mod hooks;
+mod registry;
 mod vec;
 
 pub use bundle::*;
+pub use hooks::ComponentHooks;
+pub(crate) use hooks::{HookKind, HookRegistry};
+pub use registry::ComponentRegistry;
 pub use vec::*;
 
-pub trait Component: AsAny {
+/// Components must be `Send` and `Sync`, so a `World` can be moved to and shared between threads.
+pub trait Component: AsAny + Send + Sync {
     fn as_empty_vec(&self) -> Box<dyn ComponentVec>;
+
+    /// Returns the hooks declared on the component type, registered the first time a `World` sees it.
+    /// Hooks registered through `World::component_hooks` take precedence.
+    fn declared_hooks(&self) -> Option<ComponentHooks> {
+        None
+    }
 }
 
 impl_downcast!(dyn Component);
@@ -0,0 +1,128 @@
+use std::{any::TypeId, collections::HashMap};
+
+use crate::codec::{CodecError, Decode, Encode, Reader};
+
+use super::Component;
+
+type EncodeFn = fn(&dyn Component, &mut Vec<u8>);
+type DecodeFn = fn(&mut Reader<'_>) -> Result<Box<dyn Component>, CodecError>;
+
+struct Registration {
+    type_id: TypeId,
+    name: &'static str,
+    encode: EncodeFn,
+    decode: DecodeFn,
+}
+
+/// Component types which can be encoded into a command log.
+/// Components are identified by their registration index, so every peer
+/// reading a log must register the same components in the same order.
+#[derive(Default)]
+pub struct ComponentRegistry {
+    registrations: Vec<Registration>,
+    indices: HashMap<TypeId, usize>,
+}
+
+impl ComponentRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a component type, panicking if it was already registered.
+    pub fn register<T: Component + Encode + Decode>(&mut self) -> &mut Self {
+        let name = std::any::type_name::<T>();
+
+        assert!(
+            !self.indices.contains_key(&TypeId::of::<T>()),
+            "Component {} was already registered.",
+            name
+        );
+
+        self.indices
+            .insert(TypeId::of::<T>(), self.registrations.len());
+
+        self.registrations.push(Registration {
+            type_id: TypeId::of::<T>(),
+            name,
+            encode: |component, bytes| {
+                component.downcast_ref::<T>().unwrap().encode(bytes);
+            },
+            decode: |reader| Ok(Box::new(T::decode(reader)?)),
+        });
+
+        self
+    }
+
+    #[inline]
+    pub fn contains<T: Component>(&self) -> bool {
+        self.indices.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Returns the names of the registered components, in registration order.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.registrations
+            .iter()
+            .map(|registration| registration.name)
+    }
+
+    /// Returns the type of the registered component with the given name.
+    /// Matches either the full type name or the name without its module path,
+    /// returning `None` if the name without a module path matches several components.
+    pub fn type_id_by_name(&self, name: &str) -> Option<TypeId> {
+        if let Some(registration) = self
+            .registrations
+            .iter()
+            .find(|registration| registration.name == name)
+        {
+            return Some(registration.type_id);
+        }
+
+        let mut matches = self
+            .registrations
+            .iter()
+            .filter(|registration| registration.name.rsplit("::").next() == Some(name));
+
+        match (matches.next(), matches.next()) {
+            (Some(registration), None) => Some(registration.type_id),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn index_of(&self, type_id: TypeId) -> Result<usize, CodecError> {
+        self.indices
+            .get(&type_id)
+            .copied()
+            .ok_or(CodecError::UnregisteredComponent(type_id))
+    }
+
+    fn registration(&self, idx: u64) -> Result<&Registration, CodecError> {
+        usize::try_from(idx)
+            .ok()
+            .and_then(|idx| self.registrations.get(idx))
+            .ok_or(CodecError::UnknownComponent(idx))
+    }
+
+    pub(crate) fn type_id_of(&self, idx: u64) -> Result<TypeId, CodecError> {
+        self.registration(idx)
+            .map(|registration| registration.type_id)
+    }
+
+    /// Writes the index of the component followed by its value.
+    pub(crate) fn encode(
+        &self,
+        component: &dyn Component,
+        bytes: &mut Vec<u8>,
+    ) -> Result<(), CodecError> {
+        let idx = self.index_of(component.as_any().type_id())?;
+
+        crate::codec::write_varint(idx as u64, bytes);
+        (self.registrations[idx].encode)(component, bytes);
+        Ok(())
+    }
+
+    /// Reads a component written by `encode`.
+    pub(crate) fn decode(&self, reader: &mut Reader<'_>) -> Result<Box<dyn Component>, CodecError> {
+        let registration = self.registration(reader.read_varint()?)?;
+        (registration.decode)(reader)
+    }
+}
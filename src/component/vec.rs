@@ -5,19 +5,31 @@ use crate::{
 
 /// A trait for component vectors that allows for replacing and swapping components.
 /// Indices may not correspond to entity IDs.
-pub trait ComponentVec: AsAny {
+pub trait ComponentVec: AsAny + Send + Sync {
     /// Replaces the component at the given index with the given value.
     fn replace(&mut self, idx: usize, value: Box<dyn Component>) -> Option<Box<dyn Component>>;
 
     /// Swap-removes the component at the given index and returns it.
     fn swap_remove(&mut self, idx: usize) -> Box<dyn Component>;
 
+    /// Removes the component at the given index and returns it, keeping the order of the others.
+    fn remove(&mut self, idx: usize) -> Box<dyn Component>;
+
     /// Pushes the given component to the end of the vector.
     fn push(&mut self, value: Box<dyn Component>) -> usize;
 
     /// Returns a reference to the component at the given index.
     fn get(&self, idx: usize) -> Option<&dyn Component>;
 
+    /// Returns a mutable reference to the component at the given index.
+    fn get_mut(&mut self, idx: usize) -> Option<&mut dyn Component>;
+
+    /// Swaps the components at the given indices.
+    fn swap(&mut self, a: usize, b: usize);
+
+    /// Moves the component at index `from` to index `to`, shifting the ones in between.
+    fn move_to(&mut self, from: usize, to: usize);
+
     /// Creates a new empty component vector.
     fn clone_empty(&self) -> Box<dyn ComponentVec>;
 }
@@ -51,6 +63,11 @@ impl<T: 'static + Component> ComponentVec for Vec<T> {
         Box::new(self.swap_remove(idx))
     }
 
+    #[inline]
+    fn remove(&mut self, idx: usize) -> Box<dyn Component> {
+        Box::new(self.remove(idx))
+    }
+
     #[inline]
     fn push(&mut self, component: Box<dyn Component>) -> usize {
         if let Ok(component) = component.downcast::<T>() {
@@ -66,8 +83,34 @@ impl<T: 'static + Component> ComponentVec for Vec<T> {
         self.as_slice().get(idx).map(|c| c as &dyn Component)
     }
 
+    #[inline]
+    fn get_mut(&mut self, idx: usize) -> Option<&mut dyn Component> {
+        self.as_mut_slice()
+            .get_mut(idx)
+            .map(|c| c as &mut dyn Component)
+    }
+
+    #[inline]
+    fn swap(&mut self, a: usize, b: usize) {
+        self.as_mut_slice().swap(a, b);
+    }
+
+    #[inline]
+    fn move_to(&mut self, from: usize, to: usize) {
+        move_to(self, from, to);
+    }
+
     #[inline]
     fn clone_empty(&self) -> Box<dyn ComponentVec> {
         Box::<Vec<T>>::default()
     }
 }
+
+/// Moves the element at index `from` to index `to` with a single rotation, shifting the ones in between.
+pub(crate) fn move_to<T>(slice: &mut [T], from: usize, to: usize) {
+    if from < to {
+        slice[from..=to].rotate_left(1);
+    } else {
+        slice[to..=from].rotate_right(1);
+    }
+}
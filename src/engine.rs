@@ -1,35 +1,390 @@
-use std::ops::{Deref, DerefMut};
+use std::{
+    any::{Any, TypeId},
+    collections::HashSet,
+    ops::{Deref, DerefMut},
+    panic::{self, AssertUnwindSafe},
+    time::Duration,
+};
 
-use crate::{system::System, world::World};
+use crate::{
+    event::Events,
+    plugin::{Plugin, PluginGroup},
+    system::{log_error, ErrorAction, IntoSystem, System, SystemError, SystemPanic},
+    time::{Clock, FixedTime, SystemClock, Time},
+    world::World,
+};
+
+/// A resource which stops `Engine::run_until_exit` once any `System` inserts it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AppExit {
+    Success,
+    Error(u8),
+}
+
+type Runner = Box<dyn FnMut(&mut Engine) -> AppExit + Send>;
+
+type EventUpdate = (TypeId, fn(&mut World));
+
+type ErrorHandler = Box<dyn FnMut(&SystemError) -> ErrorAction + Send>;
+
+struct SystemSlot {
+    system: Box<dyn System>,
+    enabled: bool,
+}
+
+impl SystemSlot {
+    fn new<M>(system: impl IntoSystem<M>) -> Self {
+        Self {
+            system: Box::new(system.into_system()),
+            enabled: true,
+        }
+    }
+}
+
+/// Routes `System` failures to the error handler and collects them.
+struct ErrorRouting {
+    handler: ErrorHandler,
+    catch_panics: bool,
+    errors: Vec<SystemError>,
+    aborted: bool,
+}
+
+impl ErrorRouting {
+    fn reset(&mut self) {
+        self.errors.clear();
+        self.aborted = false;
+    }
+
+    /// Runs the enabled systems in order, stopping early if the handler aborts the run.
+    fn run_systems(&mut self, systems: &mut [SystemSlot], world: &mut World) {
+        for slot in systems.iter_mut().filter(|slot| slot.enabled) {
+            if self.aborted {
+                return;
+            }
+
+            self.run_system(slot, world);
+        }
+    }
+
+    /// Runs a single system, passing its failure to the handler and applying the returned `ErrorAction`.
+    fn run_system(&mut self, slot: &mut SystemSlot, world: &mut World) {
+        let result = if self.catch_panics {
+            panic::catch_unwind(AssertUnwindSafe(|| slot.system.run(world)))
+                .unwrap_or_else(|payload| Err(Box::new(SystemPanic(panic_message(payload)))))
+        } else {
+            slot.system.run(world)
+        };
+
+        if let Err(error) = result {
+            let error = SystemError::new(slot.system.name(), error);
+
+            match (self.handler)(&error) {
+                ErrorAction::Continue => {}
+                ErrorAction::Disable => slot.enabled = false,
+                ErrorAction::Abort => self.aborted = true,
+            }
+
+            self.errors.push(error);
+        }
+    }
+}
+
+fn update_events<E: 'static + Send + Sync>(world: &mut World) {
+    if let Some(events) = world.get_resource_mut::<Events<E>>() {
+        events.update();
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&'static str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "Box<dyn Any>".to_string(),
+        },
+    }
+}
 
 /// A standalone collection of `System`s and a `World`.
-#[derive(Default)]
 pub struct Engine {
     world: World,
-    systems: Vec<Box<dyn System>>,
+    systems: Vec<SystemSlot>,
+    fixed_systems: Vec<SystemSlot>,
+    errors: ErrorRouting,
+    clock: Box<dyn Clock>,
+    runner: Option<Runner>,
+    tick_duration: Option<Duration>,
+    plugins: HashSet<TypeId>,
+    event_updates: Vec<EventUpdate>,
 }
 
 impl Engine {
     /// Pushes a `System` into the `Engine`s system list.
-    /// This is typically a function taking a mutable reference to a `World`.
+    /// This is typically a function taking a mutable reference to a `World`,
+    /// returning either nothing or a `Result`.
+    #[inline]
+    pub fn push_system<M>(&mut self, system: impl IntoSystem<M>) -> &mut Self {
+        self.systems.push(SystemSlot::new(system));
+        self
+    }
+
+    /// Pushes a `System` into the `Engine`s fixed-update system list.
+    /// Fixed-update systems run zero or more times per `run`, once for every `FixedTime` timestep elapsed.
+    #[inline]
+    pub fn push_fixed_system<M>(&mut self, system: impl IntoSystem<M>) -> &mut Self {
+        self.fixed_systems.push(SystemSlot::new(system));
+        self
+    }
+
+    /// Replaces the handler deciding what happens when a `System` returns an error.
+    /// The default handler, `log_error`, prints the error and continues.
+    pub fn set_error_handler(
+        &mut self,
+        handler: impl FnMut(&SystemError) -> ErrorAction + Send + 'static,
+    ) -> &mut Self {
+        self.errors.handler = Box::new(handler);
+        self
+    }
+
+    /// Sets whether panicking systems are caught and reported as errors instead of unwinding.
+    pub fn set_catch_panics(&mut self, catch_panics: bool) -> &mut Self {
+        self.errors.catch_panics = catch_panics;
+        self
+    }
+
+    /// Returns the errors reported by systems during the last run.
+    #[inline]
+    pub fn errors(&self) -> &[SystemError] {
+        &self.errors.errors
+    }
+
+    /// Adds a `Plugin` to the `Engine`, building it immediately.
+    /// Panics if a unique plugin is added twice, or if one of its dependencies hasn't been added yet.
+    pub fn add_plugin<P: Plugin>(&mut self, plugin: P) -> &mut Self {
+        if let Some(missing) = plugin
+            .dependencies()
+            .into_iter()
+            .find(|dependency| !self.plugins.contains(&dependency.type_id()))
+        {
+            panic!(
+                "Plugin {} requires {} to be added first.",
+                plugin.name(),
+                missing.name()
+            );
+        }
+
+        if !self.plugins.insert(TypeId::of::<P>()) && plugin.is_unique() {
+            panic!("Plugin {} was already added.", plugin.name());
+        }
+
+        plugin.build(self);
+        self
+    }
+
+    /// Adds a `PluginGroup` to the `Engine`, such as a single plugin or a tuple of plugins.
     #[inline]
-    pub fn push_system(&mut self, system: impl System + 'static) -> &mut Self {
-        self.systems.push(Box::new(system));
+    pub fn add_plugins(&mut self, plugins: impl PluginGroup) -> &mut Self {
+        plugins.add_to(self);
         self
     }
 
-    /// Updates the interal `World` by running the added `System`s.
+    /// Returns true if a plugin of type `P` has been added.
+    #[inline]
+    pub fn has_plugin<P: Plugin>(&self) -> bool {
+        self.plugins.contains(&TypeId::of::<P>())
+    }
+
+    /// Adds an event type to the `World`.
+    /// Its `Events` are updated at the start of every run, so each event lives for two runs.
+    pub fn add_event<E: 'static + Send + Sync>(&mut self) -> &mut Self {
+        let type_id = TypeId::of::<E>();
+
+        if !self.event_updates.iter().any(|(id, _)| *id == type_id) {
+            self.world.add_event::<E>();
+            self.event_updates.push((type_id, update_events::<E>));
+        }
+
+        self
+    }
+
+    /// Replaces the `Clock` used to advance `Time`.
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) -> &mut Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// Sets the duration of a single fixed-update step.
+    pub fn set_fixed_timestep(&mut self, timestep: Duration) -> &mut Self {
+        if let Some(fixed_time) = self.world.get_resource_mut::<FixedTime>() {
+            fixed_time.set_timestep(timestep);
+        } else {
+            self.world.insert_resource(FixedTime::new(timestep));
+        }
+
+        self
+    }
+
+    /// Updates the interal `World` by advancing `Time` and the added `Events`, running the fixed-update
+    /// `System`s as many times as the accumulated time allows, and then running the added `System`s.
     pub fn run(&mut self) {
-        self.systems.run(&mut self.world);
+        self.errors.reset();
+
+        let now = self.clock.now();
+
+        let delta = match self.world.get_resource_mut::<Time>() {
+            Some(time) => {
+                time.update(now);
+                time.delta()
+            }
+            None => Duration::ZERO,
+        };
+
+        if let Some(fixed_time) = self.world.get_resource_mut::<FixedTime>() {
+            fixed_time.accumulate(delta);
+        }
+
+        for (_, update) in &self.event_updates {
+            update(&mut self.world);
+        }
+
+        while self
+            .world
+            .get_resource_mut::<FixedTime>()
+            .is_some_and(FixedTime::expend)
+        {
+            self.errors
+                .run_systems(&mut self.fixed_systems, &mut self.world);
+        }
+
+        self.errors.run_systems(&mut self.systems, &mut self.world);
+    }
+
+    /// Runs the `Engine` until a `System` inserts an `AppExit` resource, which is removed and returned.
+    /// A run aborted by the error handler exits with `AppExit::Error(1)`.
+    /// If a tick rate is set, each run is paced to take at least one tick.
+    pub fn run_until_exit(&mut self) -> AppExit {
+        loop {
+            if let Some(exit) = self.tick() {
+                return exit;
+            }
+        }
+    }
+
+    /// Runs the `Engine` for the given number of ticks, stopping early if an `AppExit` is inserted.
+    pub fn run_for(&mut self, ticks: usize) -> Option<AppExit> {
+        for _ in 0..ticks {
+            if let Some(exit) = self.tick() {
+                return Some(exit);
+            }
+        }
+
+        None
+    }
+
+    /// Starts the `Engine` using its runner, which defaults to `run_until_exit`.
+    pub fn start(&mut self) -> AppExit {
+        match self.runner.take() {
+            Some(mut runner) => {
+                let exit = runner(self);
+                self.runner = Some(runner);
+                exit
+            }
+            None => self.run_until_exit(),
+        }
+    }
+
+    /// Replaces the runner used by `start`.
+    /// The runner is responsible for calling `run` until it decides to exit.
+    pub fn set_runner(
+        &mut self,
+        runner: impl FnMut(&mut Engine) -> AppExit + Send + 'static,
+    ) -> &mut Self {
+        self.runner = Some(Box::new(runner));
+        self
+    }
+
+    /// Sets the target number of runs per second for `run_until_exit` and `run_for`.
+    /// Runs finishing early wait on the `Clock` for the remainder of the tick.
+    /// Passing `None` runs as fast as possible.
+    pub fn set_tick_rate(&mut self, ticks_per_second: Option<f64>) -> &mut Self {
+        self.tick_duration = ticks_per_second.map(|rate| {
+            assert!(
+                rate.is_finite() && rate > 0.0,
+                "Tick rate must be finite and positive."
+            );
+
+            Duration::from_secs_f64(1.0 / rate)
+        });
+
+        self
+    }
+
+    /// Runs the `Engine` once with pacing, returning the `AppExit` if one was inserted.
+    fn tick(&mut self) -> Option<AppExit> {
+        if self.errors.aborted {
+            self.errors.aborted = false;
+            return Some(AppExit::Error(1));
+        }
+
+        let start = self.clock.now();
+
+        self.run();
+
+        if let Some(exit) = self.world.remove_resource::<AppExit>() {
+            return Some(exit);
+        }
+
+        if self.errors.aborted {
+            self.errors.aborted = false;
+            return Some(AppExit::Error(1));
+        }
+
+        if let Some(tick_duration) = self.tick_duration {
+            let elapsed = self.clock.now().saturating_sub(start);
+
+            if elapsed < tick_duration {
+                self.clock.sleep(tick_duration - elapsed);
+            }
+        }
+
+        None
     }
 
     /// Used to run a single `System` on the `World` for initialization purposes.
-    pub fn run_once(&mut self, mut system: impl System) -> &mut Self {
-        system.run(&mut self.world);
+    /// Failures are handled like those of added systems, and are kept in `errors` until the next run.
+    /// If the error handler aborts, the next `run_until_exit` or `run_for` exits with `AppExit::Error(1)` without running.
+    pub fn run_once<M>(&mut self, system: impl IntoSystem<M>) -> &mut Self {
+        let mut slot = SystemSlot::new(system);
+        self.errors.run_system(&mut slot, &mut self.world);
         self
     }
 }
 
+impl Default for Engine {
+    fn default() -> Self {
+        let mut world = World::default();
+        world.insert_resource(Time::default());
+        world.insert_resource(FixedTime::default());
+
+        Self {
+            world,
+            systems: Vec::new(),
+            fixed_systems: Vec::new(),
+            errors: ErrorRouting {
+                handler: Box::new(log_error),
+                catch_panics: false,
+                errors: Vec::new(),
+                aborted: false,
+            },
+            clock: Box::new(SystemClock::new()),
+            runner: None,
+            tick_duration: None,
+            plugins: HashSet::new(),
+            event_updates: Vec::new(),
+        }
+    }
+}
+
 impl Deref for Engine {
     type Target = World;
 
@@ -1,15 +1,60 @@
-use std::{any::TypeId, collections::HashMap};
+use std::{any::TypeId, cmp::Ordering, collections::HashMap, ops::Range, sync::Arc};
 
 use crate::{
     component::{Component, ComponentVec},
     sparse_set::SparseSet,
 };
 
-use super::EntityId;
+use super::{Entity, EntityId};
+
+type CompareFn = dyn Fn(&dyn ComponentVec, usize, usize) -> Ordering + Send + Sync;
+
+/// Orders the entities of an archetype by a key computed from one of their components.
+#[derive(Clone)]
+pub(crate) struct SortOrder {
+    type_id: TypeId,
+    compare: Arc<CompareFn>,
+}
+
+impl SortOrder {
+    pub fn by_key<C: Component, K: Ord>(key: impl Fn(&C) -> K + Send + Sync + 'static) -> Self {
+        Self {
+            type_id: TypeId::of::<C>(),
+            compare: Arc::new(move |column, a, b| {
+                let column = column.downcast_ref::<Vec<C>>().unwrap();
+                key(&column[a]).cmp(&key(&column[b]))
+            }),
+        }
+    }
+
+    #[inline]
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+}
+
+/// Returns the first index of the range for which `pred` is false, given that it's true for a prefix of the range.
+fn partition_point(range: Range<usize>, pred: impl Fn(usize) -> bool) -> usize {
+    let (mut low, mut high) = (range.start, range.end);
+
+    while low < high {
+        let mid = low + (high - low) / 2;
+
+        if pred(mid) {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+
+    low
+}
 
 pub struct Archetype {
     entities: SparseSet<()>,
     components: HashMap<TypeId, Box<dyn ComponentVec>>,
+    /// Kept by every insertion, removal and replacement, if set.
+    order: Option<SortOrder>,
 }
 
 impl Archetype {
@@ -23,9 +68,98 @@ impl Archetype {
         Self {
             entities: SparseSet::new(),
             components: hash_map,
+            order: None,
+        }
+    }
+
+    /// Sorts the entities, and keeps them sorted as they are inserted, removed or replaced.
+    pub(crate) fn keep_sorted(&mut self, order: SortOrder) {
+        self.sort_once(&order);
+        self.order = Some(order);
+    }
+
+    /// Stably sorts the entities once, along with their components. If the archetype keeps an order,
+    /// it's reapplied afterwards so it still holds, and the given order only breaks its ties.
+    pub(crate) fn sort(&mut self, order: &SortOrder) {
+        self.sort_once(order);
+
+        if let Some(kept) = self.order.clone() {
+            self.sort_once(&kept);
         }
     }
 
+    fn sort_once(&mut self, order: &SortOrder) {
+        let column = self.components[&order.type_id].as_ref();
+
+        let mut permutation: Vec<usize> = (0..self.len()).collect();
+        permutation.sort_by(|a, b| (order.compare)(column, *a, *b));
+
+        // Tracks where each entity currently is, and which entity is at each index, as they are swapped.
+        let mut positions: Vec<usize> = (0..permutation.len()).collect();
+        let mut entities = positions.clone();
+
+        for (idx, source) in permutation.into_iter().enumerate() {
+            let current = positions[source];
+            let displaced = entities[idx];
+
+            self.swap(idx, current);
+
+            entities[current] = displaced;
+            positions[displaced] = current;
+            entities[idx] = source;
+            positions[source] = idx;
+        }
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        if a == b {
+            return;
+        }
+
+        self.entities.swap(a, b);
+
+        for column in self.components.values_mut() {
+            column.swap(a, b);
+        }
+    }
+
+    fn compare(&self, order: &SortOrder, a: usize, b: usize) -> Ordering {
+        (order.compare)(self.components[&order.type_id].as_ref(), a, b)
+    }
+
+    /// Moves the entity at `from` to `to`, shifting the entities in between.
+    fn move_to(&mut self, from: usize, to: usize) {
+        if from == to {
+            return;
+        }
+
+        self.entities.move_to(from, to);
+
+        for column in self.components.values_mut() {
+            column.move_to(from, to);
+        }
+    }
+
+    /// Moves the entity at the given index to its place in the kept order,
+    /// found with a binary search since the other entities are sorted.
+    fn settle(&mut self, idx: usize) {
+        let Some(order) = self.order.clone() else {
+            return;
+        };
+
+        let before = |other| self.compare(&order, other, idx);
+
+        let to = if idx > 0 && before(idx - 1) == Ordering::Greater {
+            partition_point(0..idx, |other| before(other) != Ordering::Greater)
+        } else if idx + 1 < self.len() && before(idx + 1) == Ordering::Less {
+            partition_point(idx + 1..self.len(), |other| before(other) == Ordering::Less) - 1
+        } else {
+            return;
+        };
+
+        self.move_to(idx, to);
+    }
+
     /// Replaces the component of type `T` with the given component.
     pub fn replace_component(
         &mut self,
@@ -33,10 +167,14 @@ impl Archetype {
         component: Box<dyn Component>,
     ) -> Box<dyn Component> {
         if let Some(idx) = self.entities.dense_idx(id) {
-            self.components
+            let replaced = self
+                .components
                 .get_mut(&component.as_any().type_id())
                 .and_then(|components| components.replace(idx, component))
-                .unwrap_or_else(|| panic!("Entity did not have the given component."))
+                .unwrap_or_else(|| panic!("Entity did not have the given component."));
+
+            self.settle(idx);
+            replaced
         } else {
             panic!("Entity does not exist in this archetype.")
         }
@@ -54,17 +192,29 @@ impl Archetype {
                 storage.replace(dense_idx, component);
             }
         }
+
+        self.settle(dense_idx);
     }
 
     pub fn remove(&mut self, id: EntityId) -> Option<Vec<Box<dyn Component>>> {
         if let Some(idx) = self.entities.dense_idx(id) {
             let mut components = Vec::new();
 
-            for (_type_id, storage) in self.components.iter_mut() {
-                components.push(storage.swap_remove(idx));
+            // Removing without swapping keeps the order of the others.
+            if self.order.is_some() {
+                for (_type_id, storage) in self.components.iter_mut() {
+                    components.push(storage.remove(idx));
+                }
+
+                self.entities.remove_ordered(id);
+            } else {
+                for (_type_id, storage) in self.components.iter_mut() {
+                    components.push(storage.swap_remove(idx));
+                }
+
+                self.entities.remove(id);
             }
 
-            self.entities.remove(id);
             Some(components)
         } else {
             None
@@ -93,6 +243,12 @@ impl Archetype {
         None
     }
 
+    /// Returns a reference to the component with the given type of the given entity.
+    pub fn get_component_dyn(&self, id: EntityId, type_id: TypeId) -> Option<&dyn Component> {
+        let idx = self.entities.dense_idx(id)?;
+        self.components.get(&type_id)?.get(idx)
+    }
+
     pub fn get_component<T: 'static + Component>(&self, id: EntityId) -> Option<&T> {
         if let Some(idx) = self.entities.dense_idx(id) {
             return self.get_component_dense::<T>(idx);
@@ -101,7 +257,162 @@ impl Archetype {
         None
     }
 
+    /// Returns the number of entities in the archetype.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    pub(crate) fn dense_idx(&self, id: EntityId) -> Option<usize> {
+        self.entities.dense_idx(id)
+    }
+
+    pub(crate) fn entity(&self, id: EntityId) -> Option<Entity<'_>> {
+        self.dense_idx(id).map(|idx| self.entity_at(idx))
+    }
+
+    /// Returns the entity at the given dense index.
+    pub(crate) fn entity_at(&self, idx: usize) -> Entity<'_> {
+        Entity {
+            id: self.entities.ids()[idx],
+            archetype: self,
+            idx,
+        }
+    }
+
+    /// Returns the IDs of the entities, in the same order as their components.
+    #[inline]
+    pub fn entity_ids(&self) -> &[EntityId] {
+        self.entities.ids()
+    }
+
+    /// Returns the components of type `T`, in the same order as `entity_ids`.
+    pub fn column<T: Component>(&self) -> Option<&[T]> {
+        self.components
+            .get(&TypeId::of::<T>())?
+            .downcast_ref::<Vec<T>>()
+            .map(|column| column.as_slice())
+    }
+
+    /// Returns the components of type `T` mutably, in the same order as `entity_ids`.
+    pub fn column_mut<T: Component>(&mut self) -> Option<&mut [T]> {
+        self.components
+            .get_mut(&TypeId::of::<T>())?
+            .downcast_mut::<Vec<T>>()
+            .map(|column| column.as_mut_slice())
+    }
+
+    /// Returns the untyped components of the given type, in the same order as `entity_ids`.
+    pub fn column_dyn(&self, type_id: TypeId) -> Option<&dyn ComponentVec> {
+        self.components.get(&type_id).map(|column| column.as_ref())
+    }
+
+    /// Returns the entity IDs along with the untyped components of the given type, borrowed mutably.
+    pub(crate) fn column_dyn_mut(
+        &mut self,
+        type_id: TypeId,
+    ) -> Option<(&[EntityId], &mut dyn ComponentVec)> {
+        let column = self.components.get_mut(&type_id)?;
+        Some((self.entities.ids(), column.as_mut()))
+    }
+
+    /// Borrows every column for reading.
+    pub(crate) fn columns(&self) -> Columns<'_> {
+        Columns {
+            entities: self.entities.ids(),
+            shared: self
+                .components
+                .iter()
+                .map(|(type_id, column)| (*type_id, column.as_ref()))
+                .collect(),
+            unique: HashMap::new(),
+            borrowed: Vec::new(),
+        }
+    }
+
+    /// Borrows every column, so each one can be borrowed mutably once.
+    pub(crate) fn columns_mut(&mut self) -> Columns<'_> {
+        Columns {
+            entities: self.entities.ids(),
+            shared: HashMap::new(),
+            unique: self
+                .components
+                .iter_mut()
+                .map(|(type_id, column)| (*type_id, column.as_mut()))
+                .collect(),
+            borrowed: Vec::new(),
+        }
+    }
+
     pub(crate) fn entities(&self) -> &SparseSet<()> {
         &self.entities
     }
 }
+
+/// The component columns of a single archetype, split so several of them can be borrowed at once.
+pub struct Columns<'a> {
+    entities: &'a [EntityId],
+    shared: HashMap<TypeId, &'a dyn ComponentVec>,
+    unique: HashMap<TypeId, &'a mut dyn ComponentVec>,
+    /// The columns which were removed from `unique` by `get_mut`.
+    borrowed: Vec<TypeId>,
+}
+
+impl<'a> Columns<'a> {
+    #[inline]
+    pub fn entities(&self) -> &'a [EntityId] {
+        self.entities
+    }
+
+    /// Returns true if the archetype has a column of type `T`, even if it's already borrowed.
+    pub fn contains<T: Component>(&self) -> bool {
+        let type_id = TypeId::of::<T>();
+
+        self.shared.contains_key(&type_id)
+            || self.unique.contains_key(&type_id)
+            || self.borrowed.contains(&type_id)
+    }
+
+    /// Borrows the column of type `T`, panicking if it's missing or already borrowed mutably.
+    pub fn get<T: Component>(&mut self) -> &'a [T] {
+        let type_id = TypeId::of::<T>();
+
+        if let Some(column) = self.unique.remove(&type_id) {
+            self.shared.insert(type_id, column);
+        }
+
+        let column: &'a dyn ComponentVec = self
+            .shared
+            .get(&type_id)
+            .copied()
+            .unwrap_or_else(|| panic_conflict::<T>());
+
+        column.downcast_ref::<Vec<T>>().unwrap().as_slice()
+    }
+
+    /// Borrows the column of type `T` mutably, panicking if it's missing or already borrowed.
+    pub fn get_mut<T: Component>(&mut self) -> &'a mut [T] {
+        let type_id = TypeId::of::<T>();
+        self.borrowed.push(type_id);
+
+        self.unique
+            .remove(&type_id)
+            .unwrap_or_else(|| panic_conflict::<T>())
+            .downcast_mut::<Vec<T>>()
+            .unwrap()
+            .as_mut_slice()
+    }
+}
+
+fn panic_conflict<T>() -> ! {
+    panic!(
+        "Column {} is missing, or borrowed mutably more than once.",
+        std::any::type_name::<T>()
+    )
+}
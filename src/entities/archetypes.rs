@@ -1,19 +1,31 @@
-use std::{any::TypeId, collections::HashMap};
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
 
-use crate::{component::Component, query::QuerySelector};
+use crate::{
+    component::Component,
+    query::{DynamicFilter, QuerySelector},
+};
 
-use super::{Archetype, EntityId, EntityIter, IdIter, Indices};
+use super::{Archetype, EntityId, EntityIter, IdIter, Indices, SortOrder};
 
 struct QueryResult {
     filter: fn(&Archetype) -> bool,
-    indices: Vec<usize>,
+    indices: Arc<Vec<usize>>,
 }
 
 #[derive(Default)]
 pub(crate) struct Archetypes {
     archetypes: Vec<Archetype>,
     bundle_cache: HashMap<Vec<TypeId>, usize>,
-    query_cache: HashMap<TypeId, QueryResult>,
+    /// Behind a lock so queries can be cached through a shared reference.
+    query_cache: RwLock<HashMap<TypeId, QueryResult>>,
+    /// Matching archetypes of queries built at runtime, keyed by their filter.
+    dynamic_cache: RwLock<HashMap<DynamicFilter, Arc<Vec<usize>>>>,
+    /// The orders kept by archetypes with the sorted component, the first matching one wins.
+    sort_orders: Vec<SortOrder>,
 }
 
 impl Archetypes {
@@ -29,7 +41,24 @@ impl Archetypes {
             .find(|archetype| archetype.contains(id))
     }
 
-    pub fn component_archetype(&mut self, components: &[Box<dyn Component>]) -> &mut Archetype {
+    pub fn entity_archetype_idx(&self, id: EntityId) -> Option<usize> {
+        self.archetypes
+            .iter()
+            .position(|archetype| archetype.contains(id))
+    }
+
+    #[inline]
+    pub fn get(&self, idx: usize) -> &Archetype {
+        &self.archetypes[idx]
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, idx: usize) -> &mut Archetype {
+        &mut self.archetypes[idx]
+    }
+
+    /// Returns the index of the archetype storing exactly the given components, creating it if needed.
+    pub fn component_archetype_idx(&mut self, components: &[Box<dyn Component>]) -> usize {
         let type_ids: Vec<TypeId> = components
             .iter()
             .map(|component| component.as_any().type_id())
@@ -38,59 +67,151 @@ impl Archetypes {
         let idx = self.bundle_cache.entry(type_ids).or_insert_with(|| {
             let idx = self.archetypes.len();
 
-            self.archetypes.push(Archetype::from_components(components));
+            let mut archetype = Archetype::from_components(components);
+
+            if let Some(order) = self
+                .sort_orders
+                .iter()
+                .find(|order| archetype.has_component(order.type_id()))
+            {
+                archetype.keep_sorted(order.clone());
+            }
+
+            self.archetypes.push(archetype);
             let archetype = &self.archetypes[idx];
 
-            for cache in self.query_cache.values_mut() {
+            for cache in self.query_cache.get_mut().unwrap().values_mut() {
                 if (cache.filter)(archetype) {
-                    cache.indices.push(idx);
+                    Arc::make_mut(&mut cache.indices).push(idx);
+                }
+            }
+
+            for (filter, indices) in self.dynamic_cache.get_mut().unwrap().iter_mut() {
+                if filter.matches(archetype) {
+                    Arc::make_mut(indices).push(idx);
                 }
             }
 
             idx
         });
 
-        &mut self.archetypes[*idx]
+        *idx
     }
 
-    fn query_indices<T>(&mut self) -> Indices<Archetype>
+    /// Returns the archetypes matching the selector, in a stable order.
+    pub(crate) fn query_indices<T>(&self) -> Indices<'_, Archetype>
     where
         T: 'static + QuerySelector,
     {
-        let result = self
+        Indices::new(&self.archetypes, self.query_index_list::<T>())
+    }
+
+    /// Returns the sorted indices of the archetypes matching the selector.
+    pub(crate) fn query_index_list<T>(&self) -> Arc<Vec<usize>>
+    where
+        T: 'static + QuerySelector,
+    {
+        let type_id = TypeId::of::<T>();
+        let cached = self
             .query_cache
-            .entry(TypeId::of::<T>())
-            .or_insert_with(|| {
-                let indices = self
-                    .archetypes
-                    .iter()
-                    .enumerate()
-                    .filter_map(|(idx, archetype)| {
-                        if T::filter(archetype) {
-                            Some(idx)
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-
-                QueryResult {
+            .read()
+            .unwrap()
+            .get(&type_id)
+            .map(|result| result.indices.clone());
+
+        cached.unwrap_or_else(|| {
+            let indices = self
+                .archetypes
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, archetype)| {
+                    if T::filter(archetype) {
+                        Some(idx)
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+
+            self.query_cache
+                .write()
+                .unwrap()
+                .entry(type_id)
+                .or_insert_with(|| QueryResult {
                     filter: T::filter,
-                    indices,
-                }
-            });
+                    indices: Arc::new(indices),
+                })
+                .indices
+                .clone()
+        })
+    }
+
+    /// Sorts every archetype with the sorted component once.
+    pub(crate) fn sort(&mut self, order: &SortOrder) {
+        for archetype in self.archetypes.iter_mut() {
+            if archetype.has_component(order.type_id()) {
+                archetype.sort(order);
+            }
+        }
+    }
+
+    /// Keeps every archetype with the sorted component sorted, replacing any order on the same component.
+    pub(crate) fn keep_sorted(&mut self, order: SortOrder) {
+        self.sort_orders
+            .retain(|kept| kept.type_id() != order.type_id());
+        self.sort_orders.push(order);
+
+        for archetype in self.archetypes.iter_mut() {
+            if let Some(order) = self
+                .sort_orders
+                .iter()
+                .find(|order| archetype.has_component(order.type_id()))
+            {
+                archetype.keep_sorted(order.clone());
+            }
+        }
+    }
+
+    /// Returns the sorted indices of the archetypes matching a runtime filter.
+    pub(crate) fn dynamic_index_list(&self, filter: DynamicFilter) -> Arc<Vec<usize>> {
+        if let Some(indices) = self.dynamic_cache.read().unwrap().get(&filter) {
+            return indices.clone();
+        }
+
+        let indices = self
+            .archetypes
+            .iter()
+            .enumerate()
+            .filter(|(_, archetype)| filter.matches(archetype))
+            .map(|(idx, _)| idx)
+            .collect();
+
+        self.dynamic_cache
+            .write()
+            .unwrap()
+            .entry(filter)
+            .or_insert_with(|| Arc::new(indices))
+            .clone()
+    }
+
+    #[inline]
+    pub(crate) fn as_slice(&self) -> &[Archetype] {
+        &self.archetypes
+    }
 
-        Indices::new(&self.archetypes, &result.indices)
+    #[inline]
+    pub(crate) fn as_mut_slice(&mut self) -> &mut [Archetype] {
+        &mut self.archetypes
     }
 
-    pub fn query_entities<T>(&mut self) -> EntityIter
+    pub fn query_entities<T>(&self) -> EntityIter<'_>
     where
         T: 'static + QuerySelector,
     {
         self.query_indices::<T>().into()
     }
 
-    pub(crate) fn query_ids<T>(&mut self) -> IdIter
+    pub(crate) fn query_ids<T>(&self) -> IdIter<'_>
     where
         T: 'static + QuerySelector,
     {
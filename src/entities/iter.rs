@@ -1,4 +1,4 @@
-use std::iter::Enumerate;
+use std::{iter::Enumerate, sync::Arc};
 
 use crate::sparse_set;
 
@@ -26,34 +26,53 @@ impl<'archetypes> Iterator for ArchetypeIter<'archetypes> {
 
 pub(crate) struct Indices<'a, T> {
     values: &'a [T],
-    indices: std::slice::Iter<'a, usize>,
+    indices: Arc<Vec<usize>>,
+    next: usize,
 }
 
 impl<'a, T> Indices<'a, T> {
-    pub fn new(values: &'a [T], indices: &'a [usize]) -> Self {
+    pub fn new(values: &'a [T], indices: Arc<Vec<usize>>) -> Self {
         Self {
             values,
-            indices: indices.iter(),
+            indices,
+            next: 0,
         }
     }
 }
 
+impl<'a> Indices<'a, Archetype> {
+    /// Returns the number of entities in the archetypes which haven't been iterated yet.
+    pub fn entity_count(&self) -> usize {
+        self.indices[self.next..]
+            .iter()
+            .map(|idx| self.values[*idx].len())
+            .sum()
+    }
+}
+
 impl<'a, T> Iterator for Indices<'a, T> {
     type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.indices.next() {
-            Some(idx) => Some(&self.values[*idx]),
-            None => None,
-        }
+        let idx = *self.indices.get(self.next)?;
+        self.next += 1;
+        Some(&self.values[idx])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.indices.len() - self.next;
+        (remaining, Some(remaining))
     }
 }
 
+impl<'a, T> ExactSizeIterator for Indices<'a, T> {}
+
 macro_rules! impl_from_indices {
     ($ty:ident) => {
         impl<'archetypes> From<Indices<'archetypes, Archetype>> for $ty<'archetypes> {
             fn from(indices: Indices<'archetypes, Archetype>) -> Self {
                 Self {
+                    remaining: indices.entity_count(),
                     archetypes: indices,
                     iter: None,
                 }
@@ -62,9 +81,12 @@ macro_rules! impl_from_indices {
     };
 }
 
+/// Iterates the entities of the archetypes matching a query.
+/// Its length is known up front from the archetype sizes.
 pub struct EntityIter<'archetypes> {
     archetypes: Indices<'archetypes, Archetype>,
     iter: Option<ArchetypeIter<'archetypes>>,
+    remaining: usize,
 }
 
 impl<'archetype> Iterator for EntityIter<'archetype> {
@@ -74,7 +96,10 @@ impl<'archetype> Iterator for EntityIter<'archetype> {
         loop {
             match self.iter.as_mut() {
                 Some(iter) => match iter.next() {
-                    Some(entity) => return Some(entity),
+                    Some(entity) => {
+                        self.remaining -= 1;
+                        return Some(entity);
+                    }
                     None => self.iter = None,
                 },
                 None => match self.archetypes.next() {
@@ -89,13 +114,20 @@ impl<'archetype> Iterator for EntityIter<'archetype> {
             }
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
 }
 
+impl<'archetype> ExactSizeIterator for EntityIter<'archetype> {}
+
 impl_from_indices!(EntityIter);
 
 pub(crate) struct IdIter<'archetypes> {
     archetypes: Indices<'archetypes, Archetype>,
     iter: Option<sparse_set::Iter<'archetypes, ()>>,
+    remaining: usize,
 }
 
 impl<'archetype> Iterator for IdIter<'archetype> {
@@ -105,7 +137,10 @@ impl<'archetype> Iterator for IdIter<'archetype> {
         loop {
             match self.iter.as_mut() {
                 Some(iter) => match iter.next() {
-                    Some((id, _)) => return Some(*id),
+                    Some((id, _)) => {
+                        self.remaining -= 1;
+                        return Some(*id);
+                    }
                     None => self.iter = None,
                 },
                 None => match self.archetypes.next() {
@@ -115,6 +150,12 @@ impl<'archetype> Iterator for IdIter<'archetype> {
             }
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
 }
 
+impl<'archetype> ExactSizeIterator for IdIter<'archetype> {}
+
 impl_from_indices!(IdIter);
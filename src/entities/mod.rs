@@ -1,6 +1,13 @@
-use std::ops::Deref;
+use std::{
+    collections::HashSet,
+    ops::{Deref, Range},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
 
-use crate::component::Component;
+use crate::{component::Component, world::SpawnError};
 
 mod archetype;
 mod archetypes;
@@ -10,46 +17,212 @@ pub use archetype::*;
 pub(crate) use archetypes::*;
 pub use iter::*;
 
+/// State shared between a `World`'s `IdGenerator` and the buffers reserving IDs for it.
+#[derive(Default)]
+struct SharedIds {
+    next_id: AtomicUsize,
+    reserved_ids: Mutex<HashSet<EntityId>>,
+    released_ids: Mutex<Vec<EntityId>>,
+    has_released: AtomicBool,
+}
+
+/// Reserves fresh entity IDs through a shared reference, so buffers can hand out IDs before spawning.
+/// Clones share the same state as the `IdGenerator` they were created from.
+#[derive(Clone, Default)]
+pub(crate) struct IdReserver {
+    shared: Arc<SharedIds>,
+}
+
+impl IdReserver {
+    /// Takes a fresh ID which will not be handed out again until it's spawned with and despawned.
+    pub fn reserve(&self) -> EntityId {
+        let id = EntityId::new(self.shared.next_id.fetch_add(1, Ordering::Relaxed));
+        self.shared.reserved_ids.lock().unwrap().insert(id);
+        id
+    }
+
+    /// Gives back a reserved ID which will never be spawned, so it can be recycled.
+    pub fn release(&self, id: EntityId) {
+        if self.shared.reserved_ids.lock().unwrap().remove(&id) {
+            self.shared.released_ids.lock().unwrap().push(id);
+            self.shared.has_released.store(true, Ordering::Release);
+        }
+    }
+
+    /// Returns true if both reservers share the same state.
+    pub fn is_shared_with(&self, other: &IdReserver) -> bool {
+        Arc::ptr_eq(&self.shared, &other.shared)
+    }
+}
+
+/// Hands out entity IDs, recycling the IDs of despawned entities.
+/// Spawning only needs a mutable reference, while IDs reserved through the `IdReserver` are taken atomically.
+/// IDs skipped by `take` are kept as ranges, so taking a large ID doesn't allocate every skipped one.
+/// The ranges are sorted from highest to lowest, so the lowest skipped IDs are handed out first.
 pub(crate) struct IdGenerator {
+    reserver: IdReserver,
     available_ids: Vec<EntityId>,
-    next_id: usize,
+    skipped_ids: Vec<Range<usize>>,
+    /// How far past the next fresh ID `take` may go, since sparse storage grows to the largest ID.
+    max_gap: usize,
 }
 
 impl IdGenerator {
+    /// The default for how far past the next fresh ID an entity can be spawned with `take`.
+    pub const DEFAULT_MAX_GAP: usize = 1 << 16;
+
     pub fn new() -> Self {
         Self {
+            reserver: IdReserver::default(),
             available_ids: Vec::new(),
-            next_id: 0,
+            skipped_ids: Vec::new(),
+            max_gap: Self::DEFAULT_MAX_GAP,
+        }
+    }
+
+    #[inline]
+    pub fn set_max_gap(&mut self, max_gap: usize) {
+        self.max_gap = max_gap;
+    }
+
+    #[inline]
+    pub fn reserver(&self) -> &IdReserver {
+        &self.reserver
+    }
+
+    /// Moves the IDs released by dropped buffers to the available IDs.
+    fn collect_released(&mut self) {
+        let shared = &self.reserver.shared;
+
+        if shared.has_released.load(Ordering::Acquire) {
+            let mut released_ids = shared.released_ids.lock().unwrap();
+            self.available_ids.append(&mut released_ids);
+            shared.has_released.store(false, Ordering::Release);
         }
     }
 
     pub fn spawn(&mut self) -> EntityId {
-        match self.available_ids.pop() {
-            Some(id) => id,
-            None => {
-                let id = self.next_id;
-                self.next_id += 1;
-                id.into()
+        self.collect_released();
+
+        if let Some(id) = self.available_ids.pop() {
+            return id;
+        }
+
+        if let Some(range) = self.skipped_ids.last_mut() {
+            let id = EntityId::new(range.start);
+            range.start += 1;
+
+            if range.start == range.end {
+                self.skipped_ids.pop();
             }
+
+            return id;
         }
+
+        EntityId::new(self.reserver.shared.next_id.fetch_add(1, Ordering::Relaxed))
     }
 
-    pub fn despawn(&mut self, id: EntityId) {
-        self.available_ids.push(id);
+    #[inline]
+    pub fn reserve(&self) -> EntityId {
+        self.reserver.reserve()
     }
-}
 
-impl Default for IdGenerator {
-    fn default() -> Self {
-        Self::new()
+    /// Marks a reserved ID as spawned, returning false if it wasn't reserved.
+    pub fn claim(&mut self, id: EntityId) -> bool {
+        self.reserver
+            .shared
+            .reserved_ids
+            .lock()
+            .unwrap()
+            .remove(&id)
+    }
+
+    /// Takes a specific ID which isn't spawned or reserved. Fails if it's larger than `EntityId::MAX`,
+    /// or more than the maximum gap past the next fresh ID.
+    pub fn take(&mut self, id: EntityId) -> Result<(), SpawnError> {
+        if *id > EntityId::MAX {
+            return Err(SpawnError::OutOfRange(id));
+        }
+
+        // Reserved IDs can only be claimed by the buffer which reserved them.
+        if self
+            .reserver
+            .shared
+            .reserved_ids
+            .lock()
+            .unwrap()
+            .contains(&id)
+        {
+            return Err(SpawnError::InUse(id));
+        }
+
+        self.collect_released();
+
+        if let Some(idx) = self
+            .available_ids
+            .iter()
+            .position(|&available| available == id)
+        {
+            self.available_ids.swap_remove(idx);
+            return Ok(());
+        }
+
+        if let Some(idx) = self
+            .skipped_ids
+            .iter()
+            .position(|range| range.contains(&*id))
+        {
+            let range = self.skipped_ids[idx].clone();
+            let splits = [*id + 1..range.end, range.start..*id];
+
+            self.skipped_ids.splice(
+                idx..=idx,
+                splits.into_iter().filter(|split| split.start < split.end),
+            );
+
+            return Ok(());
+        }
+
+        let next_id = &self.reserver.shared.next_id;
+        let mut next = next_id.load(Ordering::Relaxed);
+
+        loop {
+            if *id < next {
+                return Err(SpawnError::InUse(id));
+            }
+
+            if *id - next >= self.max_gap {
+                return Err(SpawnError::OutOfRange(id));
+            }
+
+            match next_id.compare_exchange_weak(next, *id + 1, Ordering::Relaxed, Ordering::Relaxed)
+            {
+                Ok(_) => break,
+                Err(current) => next = current,
+            }
+        }
+
+        if next < *id {
+            self.skipped_ids.insert(0, next..*id);
+        }
+
+        Ok(())
+    }
+
+    pub fn despawn(&mut self, id: EntityId) {
+        self.available_ids.push(id);
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 #[repr(transparent)]
 pub struct EntityId(usize);
 
 impl EntityId {
+    /// The largest ID an entity can be spawned with.
+    /// IDs index sparse storage, so they are bounded to keep it addressable.
+    pub const MAX: usize = u32::MAX as usize;
+
     pub fn new(id: usize) -> Self {
         Self(id)
     }
@@ -0,0 +1,173 @@
+use std::{
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
+use crate::{system::SystemParam, world::World};
+
+/// Double-buffered storage for events of type `E`, kept as a resource.
+/// Calling `update` once per `Engine` run means every event lives for exactly two runs,
+/// so each reader sees it once no matter where it runs relative to the writer.
+pub struct Events<E> {
+    previous: Vec<E>,
+    previous_start: usize,
+    current: Vec<E>,
+    current_start: usize,
+}
+
+impl<E> Events<E> {
+    pub fn new() -> Self {
+        Self {
+            previous: Vec::new(),
+            previous_start: 0,
+            current: Vec::new(),
+            current_start: 0,
+        }
+    }
+
+    pub fn send(&mut self, event: E) {
+        self.current.push(event);
+    }
+
+    /// Drops the events sent before the last update and starts a new buffer.
+    pub fn update(&mut self) {
+        self.previous = std::mem::take(&mut self.current);
+        self.previous_start = self.current_start;
+        self.current_start += self.previous.len();
+    }
+
+    /// Drops all events, including the ones sent since the last update.
+    pub fn clear(&mut self) {
+        self.update();
+        self.update();
+    }
+
+    /// Returns the number of events currently stored.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the total number of events ever sent.
+    #[inline]
+    fn event_count(&self) -> usize {
+        self.current_start + self.current.len()
+    }
+
+    /// Returns an iterator over the stored events sent at or after the given count.
+    fn iter_from(&self, count: usize) -> impl Iterator<Item = &E> {
+        let previous = count
+            .saturating_sub(self.previous_start)
+            .min(self.previous.len());
+        let current = count
+            .saturating_sub(self.current_start)
+            .min(self.current.len());
+
+        self.previous[previous..]
+            .iter()
+            .chain(self.current[current..].iter())
+    }
+}
+
+impl<E> Default for Events<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sends events of type `E` through a mutable borrow of their `Events` resource.
+pub struct EventWriter<'w, E>(&'w mut Events<E>);
+
+impl<'w, E> EventWriter<'w, E> {
+    pub fn new(events: &'w mut Events<E>) -> Self {
+        Self(events)
+    }
+
+    #[inline]
+    pub fn send(&mut self, event: E) {
+        self.0.send(event);
+    }
+
+    pub fn send_batch(&mut self, events: impl IntoIterator<Item = E>) {
+        self.0.current.extend(events);
+    }
+}
+
+/// The position of a single reader in an `Events` queue.
+pub struct EventCursor<E> {
+    last_count: usize,
+    _marker: PhantomData<fn() -> E>,
+}
+
+impl<E> EventCursor<E> {
+    pub fn new() -> Self {
+        Self {
+            last_count: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the events that haven't been read by this cursor yet, and marks them as read.
+    pub fn read<'a>(&mut self, events: &'a Events<E>) -> impl Iterator<Item = &'a E> {
+        let iter = events.iter_from(self.last_count);
+        self.last_count = events.event_count();
+        iter
+    }
+}
+
+impl<E> Default for EventCursor<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A `SystemParam` reading events of type `E`.
+/// Every system keeps its own cursor, so each one sees every event once.
+pub struct EventReader<'s, E: 'static>(&'s mut EventCursor<E>);
+
+impl<'s, E: 'static> EventReader<'s, E> {
+    /// Returns the events that this reader hasn't seen yet.
+    pub fn read<'w>(&mut self, world: &'w World) -> impl Iterator<Item = &'w E> {
+        let events = world
+            .get_resource::<Events<E>>()
+            .unwrap_or_else(|| panic_unregistered::<E>());
+
+        self.0.read(events)
+    }
+}
+
+impl<'a, E: 'static> SystemParam for EventReader<'a, E> {
+    type State = EventCursor<E>;
+    type Item<'s> = EventReader<'s, E>;
+
+    #[inline]
+    fn fetch<'s>(state: &'s mut Self::State, _world: &World) -> Self::Item<'s> {
+        EventReader(state)
+    }
+}
+
+impl<'s, E: 'static> Deref for EventReader<'s, E> {
+    type Target = EventCursor<E>;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl<'s, E: 'static> DerefMut for EventReader<'s, E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0
+    }
+}
+
+pub(crate) fn panic_unregistered<E>() -> ! {
+    panic!(
+        "Event {} has not been added to the world.",
+        std::any::type_name::<E>()
+    )
+}
@@ -1,15 +1,20 @@
 pub mod buffer;
 pub mod changes;
+pub mod codec;
 pub mod component;
 pub mod engine;
 pub mod entities;
+pub mod event;
+pub mod plugin;
 pub mod query;
 pub mod sparse_set;
 pub mod system;
+pub mod time;
 pub mod world;
 
 mod downcast;
+mod resource;
 
 pub mod macros {
-    pub use lemon_ecs_macros::{Bundle, Component};
+    pub use lemon_ecs_macros::{Bundle, Component, QueryData, QueryFilter};
 }
@@ -0,0 +1,79 @@
+use std::any::TypeId;
+
+use lemon_ecs_macros::all_tuples;
+
+use crate::engine::Engine;
+
+/// A reusable bundle of `System`s, resources and configuration which can be added to an `Engine`.
+pub trait Plugin: 'static {
+    /// Configures the `Engine`, typically by pushing systems and inserting resources.
+    fn build(&self, engine: &mut Engine);
+
+    /// Returns the name of the plugin, used when reporting registration errors.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Returns true if adding the plugin more than once should panic.
+    fn is_unique(&self) -> bool {
+        true
+    }
+
+    /// Returns the plugins which must be added to the `Engine` before this one.
+    fn dependencies(&self) -> Vec<PluginId> {
+        Vec::new()
+    }
+}
+
+/// Identifies a `Plugin` type, used to declare dependencies between plugins.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct PluginId {
+    type_id: TypeId,
+    name: &'static str,
+}
+
+impl PluginId {
+    pub fn of<P: Plugin>() -> Self {
+        Self {
+            type_id: TypeId::of::<P>(),
+            name: std::any::type_name::<P>(),
+        }
+    }
+
+    #[inline]
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// A collection of plugins added to an `Engine` in order.
+/// Implemented for every `Plugin` and for tuples of plugin groups.
+pub trait PluginGroup {
+    fn add_to(self, engine: &mut Engine);
+}
+
+impl<P: Plugin> PluginGroup for P {
+    #[inline]
+    fn add_to(self, engine: &mut Engine) {
+        engine.add_plugin(self);
+    }
+}
+
+macro_rules! impl_tuple_plugin_group {
+    ($($t:ident),*) => {
+        impl<$($t: PluginGroup),*> PluginGroup for ($($t,)*) {
+            #[allow(non_snake_case, unused_variables)]
+            fn add_to(self, engine: &mut Engine) {
+                let ($($t,)*) = self;
+                $($t.add_to(engine);)*
+            }
+        }
+    };
+}
+
+all_tuples!(impl_tuple_plugin_group, 0..16);
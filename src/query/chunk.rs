@@ -0,0 +1,342 @@
+use std::{any::TypeId, marker::PhantomData, sync::Arc};
+
+use lemon_ecs_macros::all_tuples;
+
+use crate::{
+    component::Component,
+    entities::{Archetype, Columns, EntityId},
+};
+
+use super::{QuerySelector, With, Without};
+
+/// Selects entities with a component of type `T`, borrowing it mutably.
+pub struct Mut<T>(PhantomData<T>);
+
+impl<T: Component> QuerySelector for Mut<T> {
+    #[inline]
+    fn filter(archetype: &Archetype) -> bool {
+        archetype.has_component(TypeId::of::<T>())
+    }
+}
+
+/// Allows fetching whole component columns from an archetype.
+/// `T` fetches `&[T]`, `Mut<T>` fetches `&mut [T]` and `EntityId` fetches `&[EntityId]`.
+pub trait ChunkRetriever: QuerySelector {
+    type Chunk<'world>;
+    type Item<'world>;
+
+    /// Fetches the columns, panicking if the same column is borrowed mutably twice.
+    fn fetch_chunk<'world>(columns: &mut Columns<'world>) -> Self::Chunk<'world>;
+
+    /// Returns the row at the given index of a chunk.
+    fn chunk_item<'world>(chunk: Self::Chunk<'world>, idx: usize) -> Self::Item<'world>;
+
+    /// Splits the first row off a chunk, panicking if the chunk is empty.
+    fn split_first<'world>(chunk: Self::Chunk<'world>)
+        -> (Self::Item<'world>, Self::Chunk<'world>);
+}
+
+/// Marks chunk retrievers which never borrow a column mutably.
+pub trait ReadOnlyChunk: ChunkRetriever {}
+
+impl<T: Component> ChunkRetriever for T {
+    type Chunk<'world> = &'world [T];
+    type Item<'world> = &'world T;
+
+    #[inline]
+    fn fetch_chunk<'world>(columns: &mut Columns<'world>) -> Self::Chunk<'world> {
+        columns.get::<T>()
+    }
+
+    #[inline]
+    fn chunk_item<'world>(chunk: Self::Chunk<'world>, idx: usize) -> Self::Item<'world> {
+        &chunk[idx]
+    }
+
+    #[inline]
+    fn split_first<'world>(
+        chunk: Self::Chunk<'world>,
+    ) -> (Self::Item<'world>, Self::Chunk<'world>) {
+        chunk.split_first().unwrap()
+    }
+}
+
+impl<T: Component> ReadOnlyChunk for T {}
+
+impl<T: Component> ChunkRetriever for Mut<T> {
+    type Chunk<'world> = &'world mut [T];
+    type Item<'world> = &'world mut T;
+
+    #[inline]
+    fn fetch_chunk<'world>(columns: &mut Columns<'world>) -> Self::Chunk<'world> {
+        columns.get_mut::<T>()
+    }
+
+    #[inline]
+    fn chunk_item<'world>(chunk: Self::Chunk<'world>, idx: usize) -> Self::Item<'world> {
+        &mut chunk[idx]
+    }
+
+    #[inline]
+    fn split_first<'world>(
+        chunk: Self::Chunk<'world>,
+    ) -> (Self::Item<'world>, Self::Chunk<'world>) {
+        chunk.split_first_mut().unwrap()
+    }
+}
+
+impl ChunkRetriever for EntityId {
+    type Chunk<'world> = &'world [EntityId];
+    type Item<'world> = EntityId;
+
+    #[inline]
+    fn fetch_chunk<'world>(columns: &mut Columns<'world>) -> Self::Chunk<'world> {
+        columns.entities()
+    }
+
+    #[inline]
+    fn chunk_item<'world>(chunk: Self::Chunk<'world>, idx: usize) -> Self::Item<'world> {
+        chunk[idx]
+    }
+
+    #[inline]
+    fn split_first<'world>(
+        chunk: Self::Chunk<'world>,
+    ) -> (Self::Item<'world>, Self::Chunk<'world>) {
+        let (id, rest) = chunk.split_first().unwrap();
+        (*id, rest)
+    }
+}
+
+impl ReadOnlyChunk for EntityId {}
+
+impl<T: Component> ChunkRetriever for Option<T> {
+    type Chunk<'world> = Option<&'world [T]>;
+    type Item<'world> = Option<&'world T>;
+
+    #[inline]
+    fn fetch_chunk<'world>(columns: &mut Columns<'world>) -> Self::Chunk<'world> {
+        columns.contains::<T>().then(|| columns.get::<T>())
+    }
+
+    #[inline]
+    fn chunk_item<'world>(chunk: Self::Chunk<'world>, idx: usize) -> Self::Item<'world> {
+        chunk.map(|chunk| &chunk[idx])
+    }
+
+    #[inline]
+    fn split_first<'world>(
+        chunk: Self::Chunk<'world>,
+    ) -> (Self::Item<'world>, Self::Chunk<'world>) {
+        match chunk {
+            Some(chunk) => {
+                let (first, rest) = chunk.split_first().unwrap();
+                (Some(first), Some(rest))
+            }
+            None => (None, None),
+        }
+    }
+}
+
+impl<T: Component> ReadOnlyChunk for Option<T> {}
+
+impl<T: Component> ChunkRetriever for Option<Mut<T>> {
+    type Chunk<'world> = Option<&'world mut [T]>;
+    type Item<'world> = Option<&'world mut T>;
+
+    #[inline]
+    fn fetch_chunk<'world>(columns: &mut Columns<'world>) -> Self::Chunk<'world> {
+        columns.contains::<T>().then(|| columns.get_mut::<T>())
+    }
+
+    #[inline]
+    fn chunk_item<'world>(chunk: Self::Chunk<'world>, idx: usize) -> Self::Item<'world> {
+        chunk.map(|chunk| &mut chunk[idx])
+    }
+
+    #[inline]
+    fn split_first<'world>(
+        chunk: Self::Chunk<'world>,
+    ) -> (Self::Item<'world>, Self::Chunk<'world>) {
+        match chunk {
+            Some(chunk) => {
+                let (first, rest) = chunk.split_first_mut().unwrap();
+                (Some(first), Some(rest))
+            }
+            None => (None, None),
+        }
+    }
+}
+
+impl<T: 'static> ChunkRetriever for With<T> {
+    type Chunk<'world> = ();
+    type Item<'world> = ();
+
+    #[inline]
+    fn fetch_chunk<'world>(_columns: &mut Columns<'world>) -> Self::Chunk<'world> {}
+
+    #[inline]
+    fn chunk_item<'world>(_chunk: Self::Chunk<'world>, _idx: usize) -> Self::Item<'world> {}
+
+    #[inline]
+    fn split_first<'world>(
+        _chunk: Self::Chunk<'world>,
+    ) -> (Self::Item<'world>, Self::Chunk<'world>) {
+        ((), ())
+    }
+}
+
+impl<T: 'static> ReadOnlyChunk for With<T> {}
+
+impl<T: 'static> ChunkRetriever for Without<T> {
+    type Chunk<'world> = ();
+    type Item<'world> = ();
+
+    #[inline]
+    fn fetch_chunk<'world>(_columns: &mut Columns<'world>) -> Self::Chunk<'world> {}
+
+    #[inline]
+    fn chunk_item<'world>(_chunk: Self::Chunk<'world>, _idx: usize) -> Self::Item<'world> {}
+
+    #[inline]
+    fn split_first<'world>(
+        _chunk: Self::Chunk<'world>,
+    ) -> (Self::Item<'world>, Self::Chunk<'world>) {
+        ((), ())
+    }
+}
+
+impl<T: 'static> ReadOnlyChunk for Without<T> {}
+
+macro_rules! impl_tuple_chunk {
+    ($($t:ident),*) => {
+        impl<$($t: ChunkRetriever),*> ChunkRetriever for ($($t,)*) {
+            type Chunk<'world> = ($($t::Chunk<'world>,)*);
+            type Item<'world> = ($($t::Item<'world>,)*);
+
+            #[allow(clippy::unused_unit)]
+            fn fetch_chunk<'world>(_columns: &mut Columns<'world>) -> Self::Chunk<'world> {
+                ($($t::fetch_chunk(_columns),)*)
+            }
+
+            #[allow(non_snake_case, clippy::unused_unit)]
+            fn chunk_item<'world>(chunk: Self::Chunk<'world>, _idx: usize) -> Self::Item<'world> {
+                let ($($t,)*) = chunk;
+                ($($t::chunk_item($t, _idx),)*)
+            }
+
+            #[allow(non_snake_case, clippy::unused_unit)]
+            fn split_first<'world>(chunk: Self::Chunk<'world>) -> (Self::Item<'world>, Self::Chunk<'world>) {
+                let ($($t,)*) = chunk;
+                $(let $t = $t::split_first($t);)*
+                (($($t.0,)*), ($($t.1,)*))
+            }
+        }
+
+        impl<$($t: ReadOnlyChunk),*> ReadOnlyChunk for ($($t,)*) {}
+    };
+}
+
+all_tuples!(impl_tuple_chunk, 0..16);
+
+/// Iterates the columns of every non-empty archetype matching a read-only `ChunkRetriever`.
+pub struct ChunkIter<'world, T: ReadOnlyChunk> {
+    archetypes: &'world [Archetype],
+    indices: Arc<Vec<usize>>,
+    next: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<'world, T: ReadOnlyChunk> ChunkIter<'world, T> {
+    pub(crate) fn new(archetypes: &'world [Archetype], indices: Arc<Vec<usize>>) -> Self {
+        Self {
+            archetypes,
+            indices,
+            next: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the columns of the next archetype accepted by the function.
+    pub(crate) fn next_matching(
+        &mut self,
+        mut accept: impl FnMut(&Archetype) -> bool,
+    ) -> Option<T::Chunk<'world>> {
+        loop {
+            let archetypes = self.archetypes;
+            let archetype = &archetypes[*self.indices.get(self.next)?];
+            self.next += 1;
+
+            if accept(archetype) {
+                return Some(T::fetch_chunk(&mut archetype.columns()));
+            }
+        }
+    }
+}
+
+impl<'world, T: ReadOnlyChunk> Iterator for ChunkIter<'world, T> {
+    type Item = T::Chunk<'world>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_matching(|archetype| !archetype.is_empty())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.indices.len() - self.next))
+    }
+}
+
+/// Iterates the columns of every non-empty archetype matching a `ChunkRetriever`, allowing mutable columns.
+pub struct ChunkIterMut<'world, T: ChunkRetriever> {
+    rest: &'world mut [Archetype],
+    offset: usize,
+    indices: Arc<Vec<usize>>,
+    next: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<'world, T: ChunkRetriever> ChunkIterMut<'world, T> {
+    /// The indices must be sorted, which is the case for the cached query indices.
+    pub(crate) fn new(archetypes: &'world mut [Archetype], indices: Arc<Vec<usize>>) -> Self {
+        Self {
+            rest: archetypes,
+            offset: 0,
+            indices,
+            next: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the columns of the next archetype accepted by the function.
+    pub(crate) fn next_matching(
+        &mut self,
+        mut accept: impl FnMut(&Archetype) -> bool,
+    ) -> Option<T::Chunk<'world>> {
+        loop {
+            let idx = *self.indices.get(self.next)?;
+            self.next += 1;
+
+            // Splitting off the archetypes before the next one keeps every returned borrow disjoint.
+            let rest = std::mem::take(&mut self.rest);
+            let (archetype, rest) = rest[idx - self.offset..].split_first_mut().unwrap();
+            self.rest = rest;
+            self.offset = idx + 1;
+
+            if accept(archetype) {
+                return Some(T::fetch_chunk(&mut archetype.columns_mut()));
+            }
+        }
+    }
+}
+
+impl<'world, T: ChunkRetriever> Iterator for ChunkIterMut<'world, T> {
+    type Item = T::Chunk<'world>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_matching(|archetype| !archetype.is_empty())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.indices.len() - self.next))
+    }
+}
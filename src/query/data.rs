@@ -0,0 +1,85 @@
+use crate::{
+    component::Component,
+    entities::{Entity, EntityId},
+};
+
+use super::{ChunkRetriever, Mut};
+
+/// Field types of structs deriving `QueryData`.
+/// `Retriever` is the type fetching the field, such as `Mut<T>` for `&mut T`.
+pub trait QueryField<'world>: Sized {
+    type Retriever: ChunkRetriever;
+
+    fn from_item(item: <Self::Retriever as ChunkRetriever>::Item<'world>) -> Self;
+}
+
+/// Field types which can be retrieved through a shared reference to the `World`.
+pub trait ReadOnlyQueryField<'world>: QueryField<'world> {
+    fn retrieve(entity: &Entity<'world>) -> Self;
+}
+
+impl<'world, T: Component> QueryField<'world> for &'world T {
+    type Retriever = T;
+
+    #[inline]
+    fn from_item(item: <Self::Retriever as ChunkRetriever>::Item<'world>) -> Self {
+        item
+    }
+}
+
+impl<'world, T: Component> ReadOnlyQueryField<'world> for &'world T {
+    #[inline]
+    fn retrieve(entity: &Entity<'world>) -> Self {
+        entity.get_component::<T>().unwrap()
+    }
+}
+
+impl<'world, T: Component> QueryField<'world> for &'world mut T {
+    type Retriever = Mut<T>;
+
+    #[inline]
+    fn from_item(item: <Self::Retriever as ChunkRetriever>::Item<'world>) -> Self {
+        item
+    }
+}
+
+impl<'world, T: Component> QueryField<'world> for Option<&'world T> {
+    type Retriever = Option<T>;
+
+    #[inline]
+    fn from_item(item: <Self::Retriever as ChunkRetriever>::Item<'world>) -> Self {
+        item
+    }
+}
+
+impl<'world, T: Component> ReadOnlyQueryField<'world> for Option<&'world T> {
+    #[inline]
+    fn retrieve(entity: &Entity<'world>) -> Self {
+        entity.get_component::<T>()
+    }
+}
+
+impl<'world, T: Component> QueryField<'world> for Option<&'world mut T> {
+    type Retriever = Option<Mut<T>>;
+
+    #[inline]
+    fn from_item(item: <Self::Retriever as ChunkRetriever>::Item<'world>) -> Self {
+        item
+    }
+}
+
+impl<'world> QueryField<'world> for EntityId {
+    type Retriever = EntityId;
+
+    #[inline]
+    fn from_item(item: <Self::Retriever as ChunkRetriever>::Item<'world>) -> Self {
+        item
+    }
+}
+
+impl<'world> ReadOnlyQueryField<'world> for EntityId {
+    #[inline]
+    fn retrieve(entity: &Entity<'world>) -> Self {
+        entity.id()
+    }
+}
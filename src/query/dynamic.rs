@@ -0,0 +1,195 @@
+use std::{any::TypeId, sync::Arc};
+
+use crate::{
+    component::{Component, ComponentVec},
+    entities::{Archetype, EntityId},
+};
+
+/// The component types an archetype must have, and must not have, to match a dynamic query.
+/// Used as the key of the runtime query cache, so both lists are kept sorted.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct DynamicFilter {
+    with: Vec<TypeId>,
+    without: Vec<TypeId>,
+}
+
+impl DynamicFilter {
+    pub fn new(with: &[TypeId], without: &[TypeId]) -> Self {
+        let sorted = |type_ids: &[TypeId]| {
+            let mut type_ids = type_ids.to_vec();
+            type_ids.sort_unstable();
+            type_ids.dedup();
+            type_ids
+        };
+
+        Self {
+            with: sorted(with),
+            without: sorted(without),
+        }
+    }
+
+    pub fn matches(&self, archetype: &Archetype) -> bool {
+        self.with
+            .iter()
+            .all(|type_id| archetype.has_component(*type_id))
+            && !self
+                .without
+                .iter()
+                .any(|type_id| archetype.has_component(*type_id))
+    }
+}
+
+/// A query built at runtime from component `TypeId`s, returning untyped components.
+/// Components are returned in the order their types were passed to `World::query_dynamic`.
+pub struct DynamicQuery<'world> {
+    archetypes: &'world [Archetype],
+    indices: Arc<Vec<usize>>,
+    with: Vec<TypeId>,
+}
+
+impl<'world> DynamicQuery<'world> {
+    pub(crate) fn new(
+        archetypes: &'world [Archetype],
+        indices: Arc<Vec<usize>>,
+        with: &[TypeId],
+    ) -> Self {
+        Self {
+            archetypes,
+            indices,
+            with: with.to_vec(),
+        }
+    }
+
+    /// Returns the number of entities matching the query.
+    pub fn len(&self) -> usize {
+        self.indices
+            .iter()
+            .map(|idx| self.archetypes[*idx].len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the component columns of every non-empty matching archetype.
+    pub fn chunks(&self) -> impl Iterator<Item = DynamicChunk<'world>> + '_ {
+        let archetypes = self.archetypes;
+
+        self.indices
+            .iter()
+            .map(move |idx| &archetypes[*idx])
+            .filter(|archetype| !archetype.is_empty())
+            .map(|archetype| DynamicChunk {
+                ids: archetype.entity_ids(),
+                columns: self
+                    .with
+                    .iter()
+                    .map(|type_id| archetype.column_dyn(*type_id).unwrap())
+                    .collect(),
+            })
+    }
+}
+
+impl<'world> IntoIterator for DynamicQuery<'world> {
+    type Item = DynamicEntity<'world>;
+    type IntoIter = DynamicQueryIter<'world>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let chunks: Vec<_> = self.chunks().collect();
+
+        DynamicQueryIter {
+            chunks: chunks.into_iter(),
+            chunk: None,
+            row: 0,
+        }
+    }
+}
+
+/// The columns of a single archetype matching a `DynamicQuery`.
+pub struct DynamicChunk<'world> {
+    ids: &'world [EntityId],
+    columns: Vec<&'world dyn ComponentVec>,
+}
+
+impl<'world> DynamicChunk<'world> {
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Returns the IDs of the entities, in the same order as their components.
+    #[inline]
+    pub fn ids(&self) -> &'world [EntityId] {
+        self.ids
+    }
+
+    /// Returns the columns, in the order of the queried types.
+    #[inline]
+    pub fn columns(&self) -> &[&'world dyn ComponentVec] {
+        &self.columns
+    }
+
+    /// Returns the entity at the given row.
+    pub fn entity(&self, row: usize) -> Option<DynamicEntity<'world>> {
+        Some(DynamicEntity {
+            id: *self.ids.get(row)?,
+            components: self
+                .columns
+                .iter()
+                .map(|column| column.get(row).unwrap())
+                .collect(),
+        })
+    }
+}
+
+/// An entity returned by a `DynamicQuery`, with its components in the order of the queried types.
+pub struct DynamicEntity<'world> {
+    id: EntityId,
+    components: Vec<&'world dyn Component>,
+}
+
+impl<'world> DynamicEntity<'world> {
+    #[inline]
+    pub fn id(&self) -> EntityId {
+        self.id
+    }
+
+    #[inline]
+    pub fn components(&self) -> &[&'world dyn Component] {
+        &self.components
+    }
+
+    /// Returns the component at the given position in the queried types.
+    #[inline]
+    pub fn get(&self, idx: usize) -> Option<&'world dyn Component> {
+        self.components.get(idx).copied()
+    }
+}
+
+pub struct DynamicQueryIter<'world> {
+    chunks: std::vec::IntoIter<DynamicChunk<'world>>,
+    chunk: Option<DynamicChunk<'world>>,
+    row: usize,
+}
+
+impl<'world> Iterator for DynamicQueryIter<'world> {
+    type Item = DynamicEntity<'world>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(entity) = self.chunk.as_ref().and_then(|chunk| chunk.entity(self.row)) {
+                self.row += 1;
+                return Some(entity);
+            }
+
+            self.chunk = Some(self.chunks.next()?);
+            self.row = 0;
+        }
+    }
+}
@@ -1,8 +1,24 @@
 use std::{any::TypeId, marker::PhantomData};
 
-use crate::entities::Archetype;
+use crate::entities::{Archetype, Entity};
 
-use super::QuerySelector;
+use super::{QueryRetriever, QuerySelector};
+
+/// Selects entities with a component of type `T`, without retrieving it.
+pub struct With<T>(PhantomData<T>);
+
+impl<T: 'static> QuerySelector for With<T> {
+    fn filter(archetype: &Archetype) -> bool {
+        archetype.has_component(TypeId::of::<T>())
+    }
+}
+
+impl<T: 'static> QueryRetriever for With<T> {
+    type Output<'world> = ();
+
+    #[inline]
+    fn retrieve<'world>(_entity: &Entity<'world>) -> Self::Output<'world> {}
+}
 
 pub struct Without<T>(PhantomData<T>);
 
@@ -11,3 +27,10 @@ impl<T: 'static> QuerySelector for Without<T> {
         !archetype.has_component(TypeId::of::<T>())
     }
 }
+
+impl<T: 'static> QueryRetriever for Without<T> {
+    type Output<'world> = ();
+
+    #[inline]
+    fn retrieve<'world>(_entity: &Entity<'world>) -> Self::Output<'world> {}
+}
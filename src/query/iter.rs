@@ -1,8 +1,8 @@
 use std::marker::PhantomData;
 
-use crate::entities::EntityIter;
+use crate::entities::{EntityId, EntityIter};
 
-use super::QueryRetriever;
+use super::{Query, QueryRetriever};
 
 pub struct QueryIter<'world, T: QueryRetriever> {
     entities: EntityIter<'world>,
@@ -24,4 +24,37 @@ impl<'world, T: QueryRetriever> Iterator for QueryIter<'world, T> {
     fn next(&mut self) -> Option<Self::Item> {
         self.entities.next().map(|entity| T::retrieve(&entity))
     }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entities.size_hint()
+    }
+}
+
+impl<'world, T: QueryRetriever> ExactSizeIterator for QueryIter<'world, T> {}
+
+/// Iterates the outputs of a query for a list of entities, skipping the ones which don't match.
+pub struct QueryManyIter<'world, T: QueryRetriever, I: Iterator<Item = EntityId>> {
+    query: Query<'world, T>,
+    ids: I,
+}
+
+impl<'world, T: QueryRetriever, I: Iterator<Item = EntityId>> QueryManyIter<'world, T, I> {
+    pub fn new(query: Query<'world, T>, ids: I) -> Self {
+        Self { query, ids }
+    }
+}
+
+impl<'world, T: QueryRetriever, I: Iterator<Item = EntityId>> Iterator
+    for QueryManyIter<'world, T, I>
+{
+    type Item = T::Output<'world>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.ids.by_ref().find_map(|id| self.query.get(id))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.ids.size_hint().1)
+    }
 }
@@ -1,20 +1,40 @@
-use std::marker::PhantomData;
+use std::{fmt::Display, marker::PhantomData};
 
 use crate::{
     changes::{AddedIter, ChangeRecord, EntitySnapshot, ModifiedIter, RemovedIter, SnapshotIter},
     component::Component,
-    entities::Archetype,
+    entities::{Archetype, EntityId},
     world::World,
 };
 
 use lemon_ecs_macros::all_tuples;
 
+mod chunk;
+mod data;
+mod dynamic;
 mod filter;
 mod iter;
+mod observer;
+mod par;
+mod predicate;
 mod retriever;
+mod state;
+mod traits;
 
-pub use filter::Without;
+pub use chunk::{ChunkIter, ChunkIterMut, ChunkRetriever, Mut, ReadOnlyChunk};
+pub use data::{QueryField, ReadOnlyQueryField};
+pub use dynamic::{DynamicChunk, DynamicEntity, DynamicFilter, DynamicQuery, DynamicQueryIter};
+pub use filter::{With, Without};
+pub use observer::ObservedChanges;
+pub(crate) use observer::QueryObservers;
+pub use par::ParQuery;
+pub use predicate::{
+    WhereChunkIter, WhereChunkIterMut, WhereIter, WhereQuery, WhereQueryMut, WhereQueryState,
+    WhereRowIter,
+};
 pub use retriever::QueryRetriever;
+pub use state::{QueryState, QueryStateIter};
+pub(crate) use traits::TraitRegistry;
 
 pub use self::iter::*;
 
@@ -35,10 +55,46 @@ macro_rules! impl_tuple_selector {
 
 all_tuples!(impl_tuple_selector, 0..16);
 
-pub struct Query<'world, T: QueryRetriever>(&'world mut World, PhantomData<T>);
+/// Returned by `get_single` when the query doesn't match exactly one entity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuerySingleError {
+    NoEntities,
+    MultipleEntities,
+}
+
+impl Display for QuerySingleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuerySingleError::NoEntities => write!(f, "query matched no entities"),
+            QuerySingleError::MultipleEntities => write!(f, "query matched more than one entity"),
+        }
+    }
+}
+
+impl std::error::Error for QuerySingleError {}
+
+/// Returns the only item of the iterator, or an error if there are none or several.
+fn single_item<I: Iterator>(mut iter: I) -> Result<I::Item, QuerySingleError> {
+    let item = iter.next().ok_or(QuerySingleError::NoEntities)?;
+
+    match iter.next() {
+        Some(_) => Err(QuerySingleError::MultipleEntities),
+        None => Ok(item),
+    }
+}
+
+pub struct Query<'world, T: QueryRetriever>(&'world World, PhantomData<T>);
+
+impl<'world, T: QueryRetriever> Clone for Query<'world, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'world, T: QueryRetriever> Copy for Query<'world, T> {}
 
 impl<'world, T: QueryRetriever> Query<'world, T> {
-    pub fn new(world: &'world mut World) -> Self {
+    pub fn new(world: &'world World) -> Self {
         Self(world, PhantomData)
     }
 
@@ -46,6 +102,196 @@ impl<'world, T: QueryRetriever> Query<'world, T> {
     pub fn filter<Q: QuerySelector>(self) -> QueryIter<'world, T> {
         QueryIter::new(self.0.query_selector::<(T, Q)>())
     }
+
+    /// Returns the number of entities matching the query, without visiting them.
+    pub fn len(&self) -> usize {
+        self.0.archetypes().query_indices::<T>().entity_count()
+    }
+
+    /// Returns true if no entities match the query.
+    pub fn is_empty(&self) -> bool {
+        self.0
+            .archetypes()
+            .query_indices::<T>()
+            .all(Archetype::is_empty)
+    }
+
+    /// Returns true if the entity exists and matches the query.
+    pub fn contains(&self, id: EntityId) -> bool {
+        self.0
+            .archetypes()
+            .entity_archetype(id)
+            .is_some_and(T::filter)
+    }
+
+    /// Only returns entities with a component of type `C` satisfying the predicate.
+    /// Further predicates can be chained with `WhereQuery::where_`.
+    pub fn where_<'p, C: Component>(
+        self,
+        predicate: impl Fn(&C) -> bool + 'p,
+    ) -> WhereQuery<'world, 'p, T> {
+        WhereQuery::new(self.0).where_(predicate)
+    }
+
+    /// Returns the output of the query for the entity, if it exists and matches the query.
+    pub fn get(&self, id: EntityId) -> Option<T::Output<'world>> {
+        let world: &'world World = self.0;
+
+        world
+            .archetypes()
+            .entity_archetype(id)
+            .filter(|archetype| T::filter(archetype))
+            .and_then(|archetype| archetype.entity(id))
+            .map(|entity| T::retrieve(&entity))
+    }
+
+    /// Returns the outputs sorted by the key, ordering equal keys like the unsorted iteration.
+    pub fn iter_sorted_by_key<K: Ord>(
+        &self,
+        mut key: impl FnMut(&T::Output<'world>) -> K,
+    ) -> std::vec::IntoIter<T::Output<'world>> {
+        self.iter_sorted_by(|a, b| key(a).cmp(&key(b)))
+    }
+
+    /// Returns the outputs sorted with the comparator, ordering equal outputs like the unsorted iteration.
+    pub fn iter_sorted_by(
+        &self,
+        compare: impl FnMut(&T::Output<'world>, &T::Output<'world>) -> std::cmp::Ordering,
+    ) -> std::vec::IntoIter<T::Output<'world>> {
+        let mut outputs: Vec<_> = self.into_iter().collect();
+        outputs.sort_by(compare);
+        outputs.into_iter()
+    }
+
+    /// Returns the outputs ordered by `EntityId`, which doesn't depend on the archetypes of the entities.
+    pub fn iter_by_id(&self) -> std::vec::IntoIter<T::Output<'world>> {
+        let mut entities: Vec<_> = self.0.query_selector::<T>().collect();
+        entities.sort_unstable_by_key(|entity| entity.id());
+
+        entities
+            .iter()
+            .map(|entity| T::retrieve(entity))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    /// Returns the output for each of the entities which match the query, skipping the others.
+    pub fn iter_many<I>(&self, ids: I) -> QueryManyIter<'world, T, I::IntoIter>
+    where
+        I: IntoIterator<Item = EntityId>,
+    {
+        QueryManyIter::new(*self, ids.into_iter())
+    }
+
+    /// Returns the output for the only entity matching the query.
+    pub fn get_single(&self) -> Result<T::Output<'world>, QuerySingleError> {
+        single_item(self.into_iter())
+    }
+
+    /// Returns the output for the only entity matching the query, panicking if there are none or several.
+    pub fn single(&self) -> T::Output<'world> {
+        self.get_single()
+            .unwrap_or_else(|error| panic!("Query {} {}.", std::any::type_name::<T>(), error))
+    }
+
+    /// Returns an iterator over the component columns of every matching archetype.
+    pub fn chunks(self) -> ChunkIter<'world, T>
+    where
+        T: ReadOnlyChunk,
+    {
+        let archetypes = self.0.archetypes();
+        ChunkIter::new(archetypes.as_slice(), archetypes.query_index_list::<T>())
+    }
+
+    /// Returns a builder for iterating the query on several threads.
+    #[inline]
+    pub fn par_iter(self) -> ParQuery<'world, T> {
+        ParQuery::new(self.0)
+    }
+
+    /// Runs the function for every entity on several threads, with the default settings of `ParQuery`.
+    #[inline]
+    pub fn par_for_each(self, f: impl Fn(T::Output<'world>) + Sync) {
+        self.par_iter().for_each(f);
+    }
+}
+
+/// A query with exclusive access to the `World`, allowing components to be borrowed mutably.
+pub struct QueryMut<'world, T: QuerySelector>(&'world mut World, PhantomData<T>);
+
+impl<'world, T: QuerySelector> QueryMut<'world, T> {
+    pub fn new(world: &'world mut World) -> Self {
+        Self(world, PhantomData)
+    }
+
+    /// Returns an iterator over the component columns of every matching archetype,
+    /// where `Mut<T>` columns are borrowed mutably.
+    pub fn chunks(self) -> ChunkIterMut<'world, T>
+    where
+        T: ChunkRetriever,
+    {
+        let archetypes = self.0.archetypes_mut();
+        let indices = archetypes.query_index_list::<T>();
+
+        ChunkIterMut::new(archetypes.as_mut_slice(), indices)
+    }
+
+    /// Returns true if the entity exists and matches the query.
+    pub fn contains(&self, id: EntityId) -> bool {
+        self.0
+            .archetypes()
+            .entity_archetype(id)
+            .is_some_and(T::filter)
+    }
+
+    /// Only returns entities with a component of type `C` satisfying the predicate.
+    /// Further predicates can be chained with `WhereQueryMut::where_`.
+    pub fn where_<'p, C: Component>(
+        self,
+        predicate: impl Fn(&C) -> bool + 'p,
+    ) -> WhereQueryMut<'world, 'p, T>
+    where
+        T: ChunkRetriever,
+    {
+        WhereQueryMut::new(self.0).where_(predicate)
+    }
+
+    /// Returns the items of the entity, if it exists and matches the query.
+    pub fn get_mut(&mut self, id: EntityId) -> Option<T::Item<'_>>
+    where
+        T: ChunkRetriever,
+    {
+        let archetypes = self.0.archetypes_mut();
+        let archetype = archetypes.get_mut(archetypes.entity_archetype_idx(id)?);
+
+        if !T::filter(archetype) {
+            return None;
+        }
+
+        let idx = archetype.dense_idx(id)?;
+        Some(T::chunk_item(
+            T::fetch_chunk(&mut archetype.columns_mut()),
+            idx,
+        ))
+    }
+
+    /// Returns the items of the only entity matching the query.
+    pub fn get_single_mut(&mut self) -> Result<T::Item<'_>, QuerySingleError>
+    where
+        T: ChunkRetriever,
+    {
+        let id = single_item(self.0.query_selector::<T>().map(|entity| entity.id()))?;
+        Ok(self.get_mut(id).unwrap())
+    }
+
+    /// Returns the items of the only entity matching the query, panicking if there are none or several.
+    pub fn single_mut(&mut self) -> T::Item<'_>
+    where
+        T: ChunkRetriever,
+    {
+        self.get_single_mut()
+            .unwrap_or_else(|error| panic!("Query {} {}.", std::any::type_name::<T>(), error))
+    }
 }
 
 impl<'world, T: QueryRetriever> IntoIterator for Query<'world, T> {
@@ -57,6 +303,15 @@ impl<'world, T: QueryRetriever> IntoIterator for Query<'world, T> {
     }
 }
 
+impl<'world, T: QueryRetriever> IntoIterator for &Query<'world, T> {
+    type Item = T::Output<'world>;
+    type IntoIter = QueryIter<'world, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        (*self).into_iter()
+    }
+}
+
 pub struct QueryChanged<'world, T: Component> {
     world: &'world World,
     record: ChangeRecord,
@@ -72,6 +327,11 @@ impl<'world, T: Component> QueryChanged<'world, T> {
         }
     }
 
+    /// Returns the change tick of the last change to the component of the given entity, if it changed.
+    pub fn tick(&self, id: EntityId) -> Option<u64> {
+        self.record.ticks.get(id).copied()
+    }
+
     /// Returns an iterator over all entities that have been added or changed.
     pub fn added(self) -> AddedIter<'world, T> {
         AddedIter::new(self.world, self.record)
@@ -0,0 +1,107 @@
+use std::{any::TypeId, collections::HashMap};
+
+use crate::{
+    buffer::WorldBuffer,
+    entities::{Archetype, EntityId},
+};
+
+use super::QuerySelector;
+
+type ObserverCallback = Box<dyn Fn(EntityId, &mut WorldBuffer) + Send + Sync>;
+
+/// Entities which started or stopped matching a `QuerySelector`, in the order it happened.
+/// An entity may appear in both lists if it entered and left between two calls.
+#[derive(Default, Debug)]
+pub struct ObservedChanges {
+    pub entered: Vec<EntityId>,
+    pub left: Vec<EntityId>,
+}
+
+struct QueryObserver {
+    filter: fn(&Archetype) -> bool,
+    changes: Option<ObservedChanges>,
+    on_enter: Vec<ObserverCallback>,
+    on_leave: Vec<ObserverCallback>,
+}
+
+/// Tracks entities moving in and out of the archetypes matched by observed selectors.
+#[derive(Default)]
+pub(crate) struct QueryObservers {
+    observers: HashMap<TypeId, QueryObserver>,
+}
+
+impl QueryObservers {
+    fn observer_mut<T: QuerySelector>(&mut self) -> &mut QueryObserver {
+        self.observers
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| QueryObserver {
+                filter: T::filter,
+                changes: None,
+                on_enter: Vec::new(),
+                on_leave: Vec::new(),
+            })
+    }
+
+    pub fn on_enter<T: QuerySelector>(&mut self, callback: ObserverCallback) {
+        self.observer_mut::<T>().on_enter.push(callback);
+    }
+
+    pub fn on_leave<T: QuerySelector>(&mut self, callback: ObserverCallback) {
+        self.observer_mut::<T>().on_leave.push(callback);
+    }
+
+    /// Takes the changes recorded for the selector.
+    /// The first call starts recording and returns `None`.
+    pub fn take_changes<T: QuerySelector>(&mut self) -> Option<ObservedChanges> {
+        let observer = self.observer_mut::<T>();
+
+        match observer.changes.as_mut() {
+            Some(changes) => Some(std::mem::take(changes)),
+            None => {
+                observer.changes = Some(ObservedChanges::default());
+                None
+            }
+        }
+    }
+
+    /// Records an entity moving between archetypes, where `None` means it was spawned or despawned.
+    pub fn transition(
+        &mut self,
+        id: EntityId,
+        old: Option<&Archetype>,
+        new: Option<&Archetype>,
+        buffer: &mut WorldBuffer,
+    ) {
+        for observer in self.observers.values_mut() {
+            let was_matching = old.is_some_and(observer.filter);
+            let is_matching = new.is_some_and(observer.filter);
+
+            if was_matching == is_matching {
+                continue;
+            }
+
+            let (list, callbacks) = if is_matching {
+                (
+                    observer
+                        .changes
+                        .as_mut()
+                        .map(|changes| &mut changes.entered),
+                    &observer.on_enter,
+                )
+            } else {
+                (
+                    observer.changes.as_mut().map(|changes| &mut changes.left),
+                    &observer.on_leave,
+                )
+            };
+
+            if let Some(list) = list {
+                list.push(id);
+            }
+
+            for callback in callbacks {
+                callback(id, buffer);
+            }
+        }
+    }
+}
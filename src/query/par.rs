@@ -0,0 +1,155 @@
+use std::{
+    marker::PhantomData,
+    ops::Range,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use crate::{
+    buffer::{ParallelBuffer, WorldBuffer},
+    entities::Archetype,
+    world::World,
+};
+
+use super::{QueryRetriever, QuerySelector};
+
+/// Runs a function for every entity of a query on a pool of scoped threads.
+/// Matching archetypes are split into batches of at most `batch_size` entities.
+///
+/// By default, threads take the next unprocessed batch as they become free.
+/// In deterministic mode, batch `i` always runs on thread `i % threads`,
+/// and buffers are recorded per batch so they merge in the same order as sequential iteration.
+pub struct ParQuery<'world, T: QueryRetriever, F: QuerySelector = ()> {
+    world: &'world World,
+    batch_size: usize,
+    threads: usize,
+    deterministic: bool,
+    _marker: PhantomData<fn() -> (T, F)>,
+}
+
+impl<'world, T: QueryRetriever, F: QuerySelector> ParQuery<'world, T, F> {
+    pub fn new(world: &'world World) -> Self {
+        Self {
+            world,
+            batch_size: 256,
+            threads: thread::available_parallelism()
+                .map(|threads| threads.get())
+                .unwrap_or(1),
+            deterministic: false,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Only runs for entities which also match the selector `Q`.
+    pub fn filter<Q: QuerySelector>(self) -> ParQuery<'world, T, Q> {
+        ParQuery {
+            world: self.world,
+            batch_size: self.batch_size,
+            threads: self.threads,
+            deterministic: self.deterministic,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Sets the maximum number of entities per batch, panicking if it's zero.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        assert!(batch_size > 0, "Batch size must be greater than zero.");
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Sets the maximum number of threads, panicking if it's zero.
+    pub fn threads(mut self, threads: usize) -> Self {
+        assert!(threads > 0, "Thread count must be greater than zero.");
+        self.threads = threads;
+        self
+    }
+
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    fn batches(&self) -> Vec<(&'world Archetype, Range<usize>)> {
+        let mut batches = Vec::new();
+
+        for archetype in self.world.archetypes().query_indices::<(T, F)>() {
+            for start in (0..archetype.len()).step_by(self.batch_size) {
+                let end = (start + self.batch_size).min(archetype.len());
+                batches.push((archetype, start..end));
+            }
+        }
+
+        batches
+    }
+
+    /// Runs `f` for every batch, passing the thread index and the batch index.
+    fn run_batches(&self, f: impl Fn(usize, usize, &'world Archetype, Range<usize>) + Sync) {
+        let batches = self.batches();
+        let threads = self.threads.min(batches.len());
+
+        if threads <= 1 {
+            for (idx, (archetype, rows)) in batches.into_iter().enumerate() {
+                f(0, idx, archetype, rows);
+            }
+
+            return;
+        }
+
+        let next = AtomicUsize::new(0);
+        let (batches, next, f) = (&batches, &next, &f);
+        let deterministic = self.deterministic;
+
+        thread::scope(|scope| {
+            for thread in 0..threads {
+                scope.spawn(move || {
+                    let mut idx = thread;
+
+                    loop {
+                        if !deterministic {
+                            idx = next.fetch_add(1, Ordering::Relaxed);
+                        }
+
+                        let Some((archetype, rows)) = batches.get(idx) else {
+                            break;
+                        };
+
+                        f(thread, idx, archetype, rows.clone());
+
+                        if deterministic {
+                            idx += threads;
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    pub fn for_each(self, f: impl Fn(T::Output<'world>) + Sync) {
+        self.run_batches(|_, _, archetype, rows| {
+            for idx in rows {
+                f(T::retrieve(&archetype.entity_at(idx)));
+            }
+        });
+    }
+
+    /// Runs `f` with a `WorldBuffer` local to the current thread, or to the current batch in deterministic mode.
+    /// The buffers are submitted to the `ParallelBuffer` with the given system order.
+    pub fn for_each_with_buffer(
+        self,
+        buffer: &ParallelBuffer,
+        order: usize,
+        f: impl Fn(&mut WorldBuffer, T::Output<'world>) + Sync,
+    ) {
+        let deterministic = self.deterministic;
+
+        self.run_batches(|thread, batch, archetype, rows| {
+            let key = if deterministic { batch } else { thread };
+            let mut local = buffer.local(order, key);
+
+            for idx in rows {
+                f(&mut local, T::retrieve(&archetype.entity_at(idx)));
+            }
+        });
+    }
+}
@@ -0,0 +1,445 @@
+use std::{any::TypeId, marker::PhantomData, sync::Arc, vec};
+
+use crate::{
+    component::{Component, ComponentVec},
+    entities::{Archetype, EntityId},
+    world::World,
+};
+
+use super::{
+    ChunkIter, ChunkIterMut, ChunkRetriever, QueryRetriever, QuerySelector, QueryState,
+    ReadOnlyChunk,
+};
+
+type RetainFn<'p> = dyn Fn(&dyn ComponentVec, &mut Vec<usize>) + 'p;
+
+/// A predicate on the components of type `type_id`, which keeps the rows of a column satisfying it.
+struct Predicate<'p> {
+    type_id: TypeId,
+    retain: Box<RetainFn<'p>>,
+}
+
+/// The predicates of a query, checked one archetype column at a time.
+pub(crate) struct Predicates<'p>(Vec<Predicate<'p>>);
+
+impl<'p> Predicates<'p> {
+    fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    fn push<C: Component>(&mut self, predicate: impl Fn(&C) -> bool + 'p) {
+        self.0.push(Predicate {
+            type_id: TypeId::of::<C>(),
+            retain: Box::new(move |column, rows| {
+                let column = column.downcast_ref::<Vec<C>>().unwrap();
+                rows.retain(|idx| predicate(&column[*idx]));
+            }),
+        });
+    }
+
+    /// Keeps the rows of the archetype satisfying every predicate,
+    /// returning false if there are none left or the archetype lacks a predicate's component.
+    fn retain(&self, archetype: &Archetype, rows: &mut Vec<usize>) -> bool {
+        if !self
+            .0
+            .iter()
+            .all(|predicate| archetype.has_component(predicate.type_id))
+        {
+            rows.clear();
+            return false;
+        }
+
+        for predicate in self.0.iter() {
+            if rows.is_empty() {
+                break;
+            }
+
+            (predicate.retain)(archetype.column_dyn(predicate.type_id).unwrap(), rows);
+        }
+
+        !rows.is_empty()
+    }
+
+    /// Replaces the rows with those of the archetype satisfying every predicate, returning false if there are none.
+    fn select(&self, archetype: &Archetype, rows: &mut Vec<usize>) -> bool {
+        rows.clear();
+        rows.extend(0..archetype.len());
+        self.retain(archetype, rows)
+    }
+
+    /// Returns true if the entity at the given row of the archetype satisfies every predicate.
+    fn matches(&self, archetype: &Archetype, idx: usize) -> bool {
+        self.retain(archetype, &mut vec![idx])
+    }
+}
+
+/// A query which only returns entities whose components satisfy every predicate.
+/// Predicates are checked during iteration, one column at a time, for the archetypes selected by `T`
+/// which have every component the predicates take.
+pub struct WhereQuery<'world, 'p, T: QueryRetriever> {
+    world: &'world World,
+    predicates: Predicates<'p>,
+    _marker: PhantomData<T>,
+}
+
+impl<'world, 'p, T: QueryRetriever> WhereQuery<'world, 'p, T> {
+    pub(crate) fn new(world: &'world World) -> Self {
+        Self {
+            world,
+            predicates: Predicates::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Only returns entities with a component of type `C` satisfying the predicate.
+    pub fn where_<C: Component>(mut self, predicate: impl Fn(&C) -> bool + 'p) -> Self {
+        self.predicates.push(predicate);
+        self
+    }
+
+    /// Returns true if the entity exists, matches the query and satisfies every predicate.
+    pub fn contains(&self, id: EntityId) -> bool {
+        self.get(id).is_some()
+    }
+
+    /// Returns the output of the query for the entity, if it matches and satisfies every predicate.
+    pub fn get(&self, id: EntityId) -> Option<T::Output<'world>> {
+        let world: &'world World = self.world;
+
+        let archetype = world
+            .archetypes()
+            .entity_archetype(id)
+            .filter(|archetype| T::filter(archetype))?;
+
+        let idx = archetype.dense_idx(id)?;
+
+        self.predicates
+            .matches(archetype, idx)
+            .then(|| T::retrieve(&archetype.entity_at(idx)))
+    }
+
+    /// Returns the number of entities satisfying every predicate, checking each of them.
+    pub fn count(&self) -> usize {
+        let mut rows = Vec::new();
+
+        self.archetypes()
+            .map(|archetype| {
+                self.predicates.select(archetype, &mut rows);
+                rows.len()
+            })
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let mut rows = Vec::new();
+
+        !self
+            .archetypes()
+            .any(|archetype| self.predicates.select(archetype, &mut rows))
+    }
+
+    /// Returns an iterator over the component columns of every matching archetype,
+    /// along with the rows satisfying every predicate.
+    pub fn chunks(self) -> WhereChunkIter<'world, 'p, T>
+    where
+        T: ReadOnlyChunk,
+    {
+        let archetypes = self.world.archetypes();
+
+        WhereChunkIter {
+            chunks: ChunkIter::new(archetypes.as_slice(), archetypes.query_index_list::<T>()),
+            predicates: self.predicates,
+        }
+    }
+
+    /// Returns the archetypes selected by `T`.
+    fn archetypes(&self) -> impl Iterator<Item = &'world Archetype> {
+        let archetypes = self.world.archetypes();
+        let slice = archetypes.as_slice();
+        let indices = archetypes.query_index_list::<T>();
+
+        (0..indices.len()).map(move |idx| &slice[indices[idx]])
+    }
+}
+
+impl<'world, 'p, T: QueryRetriever> IntoIterator for WhereQuery<'world, 'p, T> {
+    type Item = T::Output<'world>;
+    type IntoIter = WhereIter<'world, 'p, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let archetypes = self.world.archetypes();
+
+        WhereIter {
+            archetypes: archetypes.as_slice(),
+            indices: archetypes.query_index_list::<T>(),
+            next: 0,
+            archetype: None,
+            rows: Vec::new().into_iter(),
+            predicates: self.predicates,
+            _marker: PhantomData,
+        }
+    }
+}
+
+pub struct WhereIter<'world, 'p, T: QueryRetriever> {
+    archetypes: &'world [Archetype],
+    indices: Arc<Vec<usize>>,
+    next: usize,
+    archetype: Option<&'world Archetype>,
+    rows: vec::IntoIter<usize>,
+    predicates: Predicates<'p>,
+    _marker: PhantomData<T>,
+}
+
+impl<'world, 'p, T: QueryRetriever> Iterator for WhereIter<'world, 'p, T> {
+    type Item = T::Output<'world>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(idx) = self.rows.next() {
+                let archetype = self.archetype.unwrap();
+                return Some(T::retrieve(&archetype.entity_at(idx)));
+            }
+
+            let archetypes = self.archetypes;
+            let archetype = &archetypes[*self.indices.get(self.next)?];
+            self.next += 1;
+
+            let mut rows = Vec::new();
+
+            if self.predicates.select(archetype, &mut rows) {
+                self.archetype = Some(archetype);
+                self.rows = rows.into_iter();
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.indices[self.next..]
+            .iter()
+            .map(|idx| self.archetypes[*idx].len())
+            .sum::<usize>();
+
+        (0, Some(self.rows.len() + remaining))
+    }
+}
+
+/// A mutable query which only returns entities whose components satisfy every predicate.
+pub struct WhereQueryMut<'world, 'p, T: ChunkRetriever> {
+    world: &'world mut World,
+    predicates: Predicates<'p>,
+    _marker: PhantomData<T>,
+}
+
+impl<'world, 'p, T: ChunkRetriever> WhereQueryMut<'world, 'p, T> {
+    pub(crate) fn new(world: &'world mut World) -> Self {
+        Self {
+            world,
+            predicates: Predicates::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Only returns entities with a component of type `C` satisfying the predicate.
+    pub fn where_<C: Component>(mut self, predicate: impl Fn(&C) -> bool + 'p) -> Self {
+        self.predicates.push(predicate);
+        self
+    }
+
+    /// Returns true if the entity exists, matches the query and satisfies every predicate.
+    pub fn contains(&self, id: EntityId) -> bool {
+        self.world
+            .archetypes()
+            .entity_archetype(id)
+            .filter(|archetype| T::filter(archetype))
+            .and_then(|archetype| Some((archetype, archetype.dense_idx(id)?)))
+            .is_some_and(|(archetype, idx)| self.predicates.matches(archetype, idx))
+    }
+
+    /// Returns the items of the entity, if it matches the query and satisfies every predicate.
+    pub fn get_mut(&mut self, id: EntityId) -> Option<T::Item<'_>> {
+        let archetypes = self.world.archetypes_mut();
+        let archetype = archetypes.get_mut(archetypes.entity_archetype_idx(id)?);
+
+        if !T::filter(archetype) {
+            return None;
+        }
+
+        let idx = archetype.dense_idx(id)?;
+
+        if !self.predicates.matches(archetype, idx) {
+            return None;
+        }
+
+        Some(T::chunk_item(
+            T::fetch_chunk(&mut archetype.columns_mut()),
+            idx,
+        ))
+    }
+
+    /// Returns an iterator over the component columns of every matching archetype,
+    /// along with the rows satisfying every predicate. `Mut<T>` columns are borrowed mutably.
+    pub fn chunks(self) -> WhereChunkIterMut<'world, 'p, T> {
+        let archetypes = self.world.archetypes_mut();
+        let indices = archetypes.query_index_list::<T>();
+
+        WhereChunkIterMut {
+            chunks: ChunkIterMut::new(archetypes.as_mut_slice(), indices),
+            predicates: self.predicates,
+        }
+    }
+}
+
+impl<'world, 'p, T: ChunkRetriever> IntoIterator for WhereQueryMut<'world, 'p, T> {
+    type Item = T::Item<'world>;
+    type IntoIter = WhereRowIter<'world, T, WhereChunkIterMut<'world, 'p, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        WhereRowIter::new(self.chunks())
+    }
+}
+
+/// A `QueryState` which only returns entities whose components satisfy every predicate.
+pub struct WhereQueryState<'s, 'p, T: ChunkRetriever, F: QuerySelector = ()> {
+    state: &'s mut QueryState<T, F>,
+    predicates: Predicates<'p>,
+}
+
+impl<'s, 'p, T: ChunkRetriever, F: QuerySelector> WhereQueryState<'s, 'p, T, F> {
+    pub(crate) fn new(state: &'s mut QueryState<T, F>) -> Self {
+        Self {
+            state,
+            predicates: Predicates::new(),
+        }
+    }
+
+    /// Only returns entities with a component of type `C` satisfying the predicate.
+    pub fn where_<C: Component>(mut self, predicate: impl Fn(&C) -> bool + 'p) -> Self {
+        self.predicates.push(predicate);
+        self
+    }
+
+    /// Returns the items of every matching entity satisfying every predicate.
+    pub fn iter<'world>(
+        self,
+        world: &'world World,
+    ) -> WhereRowIter<'world, T, WhereChunkIter<'world, 'p, T>>
+    where
+        T: ReadOnlyChunk,
+    {
+        WhereRowIter::new(WhereChunkIter {
+            chunks: self.state.chunks(world),
+            predicates: self.predicates,
+        })
+    }
+
+    /// Returns the items of every matching entity satisfying every predicate, allowing mutable components.
+    pub fn iter_mut<'world>(
+        self,
+        world: &'world mut World,
+    ) -> WhereRowIter<'world, T, WhereChunkIterMut<'world, 'p, T>> {
+        WhereRowIter::new(WhereChunkIterMut {
+            chunks: self.state.chunks_mut(world),
+            predicates: self.predicates,
+        })
+    }
+}
+
+/// Iterates the columns of the archetypes with entities satisfying every predicate, along with their rows.
+pub struct WhereChunkIter<'world, 'p, T: ReadOnlyChunk> {
+    chunks: ChunkIter<'world, T>,
+    predicates: Predicates<'p>,
+}
+
+impl<'world, 'p, T: ReadOnlyChunk> Iterator for WhereChunkIter<'world, 'p, T> {
+    type Item = (Vec<usize>, T::Chunk<'world>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut rows = Vec::new();
+        let chunk = self
+            .chunks
+            .next_matching(|archetype| self.predicates.select(archetype, &mut rows))?;
+
+        Some((rows, chunk))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chunks.size_hint()
+    }
+}
+
+/// Iterates the columns of the archetypes with entities satisfying every predicate, along with their rows,
+/// allowing mutable columns.
+pub struct WhereChunkIterMut<'world, 'p, T: ChunkRetriever> {
+    chunks: ChunkIterMut<'world, T>,
+    predicates: Predicates<'p>,
+}
+
+impl<'world, 'p, T: ChunkRetriever> Iterator for WhereChunkIterMut<'world, 'p, T> {
+    type Item = (Vec<usize>, T::Chunk<'world>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut rows = Vec::new();
+        let chunk = self
+            .chunks
+            .next_matching(|archetype| self.predicates.select(archetype, &mut rows))?;
+
+        Some((rows, chunk))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chunks.size_hint()
+    }
+}
+
+/// Iterates the given rows of chunks, as returned by `WhereChunkIter` and `WhereChunkIterMut`.
+pub struct WhereRowIter<'world, T: ChunkRetriever, I> {
+    chunks: I,
+    chunk: Option<T::Chunk<'world>>,
+    rows: vec::IntoIter<usize>,
+    /// The row the current chunk starts at.
+    offset: usize,
+}
+
+impl<'world, T: ChunkRetriever, I> WhereRowIter<'world, T, I> {
+    fn new(chunks: I) -> Self {
+        Self {
+            chunks,
+            chunk: None,
+            rows: Vec::new().into_iter(),
+            offset: 0,
+        }
+    }
+}
+
+impl<'world, T, I> Iterator for WhereRowIter<'world, T, I>
+where
+    T: ChunkRetriever,
+    I: Iterator<Item = (Vec<usize>, T::Chunk<'world>)>,
+{
+    type Item = T::Item<'world>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(row) = self.rows.next() {
+                let mut chunk = self.chunk.take().unwrap();
+
+                // The rows are increasing, so the ones skipped before this one are split off and dropped.
+                for _ in self.offset..row {
+                    chunk = T::split_first(chunk).1;
+                }
+
+                let (item, rest) = T::split_first(chunk);
+                self.chunk = Some(rest);
+                self.offset = row + 1;
+
+                return Some(item);
+            }
+
+            let (rows, chunk) = self.chunks.next()?;
+            self.rows = rows.into_iter();
+            self.chunk = Some(chunk);
+            self.offset = 0;
+        }
+    }
+}
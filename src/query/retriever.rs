@@ -48,6 +48,23 @@ impl QuerySelector for EntityId {
     }
 }
 
+/// Selects every entity, whether or not it matches `T`.
+impl<T: QuerySelector> QuerySelector for Option<T> {
+    #[inline]
+    fn filter(_archetype: &Archetype) -> bool {
+        true
+    }
+}
+
+/// Retrieves the component of type `T` if the entity has one.
+impl<T: 'static + Component> QueryRetriever for Option<T> {
+    type Output<'world> = Option<&'world T>;
+
+    fn retrieve<'world>(entity: &Entity<'world>) -> Self::Output<'world> {
+        entity.get_component::<T>()
+    }
+}
+
 macro_rules! impl_tuple_retriever {
     ($($t:ident),*) => {
         impl<$($t: QueryRetriever),*> QueryRetriever for ($($t,)*) where Self: QuerySelector {
@@ -0,0 +1,169 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use crate::{
+    component::Component,
+    entities::{EntityId, IdReserver},
+    world::World,
+};
+
+use super::{
+    ChunkIter, ChunkIterMut, ChunkRetriever, QuerySelector, ReadOnlyChunk, WhereQueryState,
+};
+
+/// A query which keeps the indices of its matching archetypes between uses.
+/// Only archetypes created since the last use are checked, and component columns
+/// are looked up once per archetype rather than once per entity.
+///
+/// Meant to be kept across runs, for example in a system's `Local`.
+/// Panics if used with a different `World` than the first one it was used with.
+pub struct QueryState<T: ChunkRetriever, F: QuerySelector = ()> {
+    world: Option<IdReserver>,
+    matched: Arc<Vec<usize>>,
+    checked: usize,
+    _marker: PhantomData<fn() -> (T, F)>,
+}
+
+impl<T: ChunkRetriever, F: QuerySelector> QueryState<T, F> {
+    pub fn new(world: &World) -> Self {
+        let mut state = Self::default();
+        state.update_archetypes(world);
+        state
+    }
+
+    /// Matches the archetypes created since the last update.
+    pub fn update_archetypes(&mut self, world: &World) {
+        match &self.world {
+            Some(reserver) => assert!(
+                reserver.is_shared_with(world.id_reserver()),
+                "QueryState {} was used with a different world.",
+                std::any::type_name::<T>()
+            ),
+            None => self.world = Some(world.id_reserver().clone()),
+        }
+
+        let archetypes = world.archetypes().as_slice();
+
+        if self.checked == archetypes.len() {
+            return;
+        }
+
+        let matched = Arc::make_mut(&mut self.matched);
+
+        for (idx, archetype) in archetypes.iter().enumerate().skip(self.checked) {
+            if <(T, F)>::filter(archetype) {
+                matched.push(idx);
+            }
+        }
+
+        self.checked = archetypes.len();
+    }
+
+    /// Returns the number of entities matching the query.
+    pub fn len(&mut self, world: &World) -> usize {
+        self.update_archetypes(world);
+
+        let archetypes = world.archetypes().as_slice();
+        self.matched.iter().map(|idx| archetypes[*idx].len()).sum()
+    }
+
+    /// Returns true if no entities match the query.
+    pub fn is_empty(&mut self, world: &World) -> bool {
+        self.len(world) == 0
+    }
+
+    /// Returns the items of every matching entity.
+    pub fn iter<'world>(
+        &mut self,
+        world: &'world World,
+    ) -> QueryStateIter<'world, T, ChunkIter<'world, (EntityId, T)>>
+    where
+        T: ReadOnlyChunk,
+    {
+        QueryStateIter::new(self.chunks(world))
+    }
+
+    /// Returns the items of every matching entity, allowing mutable components.
+    pub fn iter_mut<'world>(
+        &mut self,
+        world: &'world mut World,
+    ) -> QueryStateIter<'world, T, ChunkIterMut<'world, (EntityId, T)>> {
+        QueryStateIter::new(self.chunks_mut(world))
+    }
+
+    /// Only returns entities with a component of type `C` satisfying the predicate.
+    /// Further predicates can be chained with `WhereQueryState::where_`.
+    pub fn where_<'s, 'p, C: Component>(
+        &'s mut self,
+        predicate: impl Fn(&C) -> bool + 'p,
+    ) -> WhereQueryState<'s, 'p, T, F> {
+        WhereQueryState::new(self).where_(predicate)
+    }
+
+    /// Returns the `C` columns of every matching archetype.
+    pub(crate) fn chunks<'world, C: ReadOnlyChunk>(
+        &mut self,
+        world: &'world World,
+    ) -> ChunkIter<'world, C> {
+        self.update_archetypes(world);
+        ChunkIter::new(world.archetypes().as_slice(), self.matched.clone())
+    }
+
+    /// Returns the `C` columns of every matching archetype, allowing mutable columns.
+    pub(crate) fn chunks_mut<'world, C: ChunkRetriever>(
+        &mut self,
+        world: &'world mut World,
+    ) -> ChunkIterMut<'world, C> {
+        self.update_archetypes(world);
+        ChunkIterMut::new(world.archetypes_mut().as_mut_slice(), self.matched.clone())
+    }
+}
+
+impl<T: ChunkRetriever, F: QuerySelector> Default for QueryState<T, F> {
+    fn default() -> Self {
+        Self {
+            world: None,
+            matched: Arc::default(),
+            checked: 0,
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Iterates the rows of the chunks of a `QueryState`.
+pub struct QueryStateIter<'world, T: ChunkRetriever, I> {
+    chunks: I,
+    chunk: Option<T::Chunk<'world>>,
+    rows: usize,
+}
+
+impl<'world, T: ChunkRetriever, I> QueryStateIter<'world, T, I> {
+    fn new(chunks: I) -> Self {
+        Self {
+            chunks,
+            chunk: None,
+            rows: 0,
+        }
+    }
+}
+
+impl<'world, T, I> Iterator for QueryStateIter<'world, T, I>
+where
+    T: ChunkRetriever,
+    I: Iterator<Item = (&'world [EntityId], T::Chunk<'world>)>,
+{
+    type Item = T::Item<'world>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.rows == 0 {
+            let (ids, chunk) = self.chunks.next()?;
+            self.rows = ids.len();
+            self.chunk = Some(chunk);
+        }
+
+        let (item, rest) = T::split_first(self.chunk.take().unwrap());
+        self.chunk = Some(rest);
+        self.rows -= 1;
+
+        Some(item)
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use crate::{
+    component::Component,
+    entities::{Archetype, EntityId},
+};
+
+type Cast<Tr> = Box<dyn Fn(&dyn Component) -> &Tr + Send + Sync>;
+type CastMut<Tr> = Box<dyn Fn(&mut dyn Component) -> &mut Tr + Send + Sync>;
+
+/// Converts components of a single type into the trait object `Tr`.
+struct TraitImpl<Tr: ?Sized> {
+    component: TypeId,
+    name: &'static str,
+    cast: Cast<Tr>,
+    cast_mut: CastMut<Tr>,
+}
+
+/// The archetypes containing implementors of a trait, as pairs of archetype and implementor indices.
+/// Only archetypes created since the last query are checked.
+#[derive(Default)]
+struct TraitMatches {
+    pairs: Arc<Vec<(usize, usize)>>,
+    checked: usize,
+}
+
+/// The component types implementing each trait queried with `World::query_trait`.
+#[derive(Default)]
+pub(crate) struct TraitRegistry {
+    /// Maps `TypeId::of::<dyn Trait>()` to a `Vec<TraitImpl<dyn Trait>>`.
+    impls: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+    matches: RwLock<HashMap<TypeId, TraitMatches>>,
+}
+
+impl TraitRegistry {
+    /// Registers `C` as an implementor of `Tr`, panicking if it was already registered.
+    pub fn register<Tr, C>(&mut self, cast: fn(&C) -> &Tr, cast_mut: fn(&mut C) -> &mut Tr)
+    where
+        Tr: ?Sized + 'static,
+        C: Component,
+    {
+        let impls = self
+            .impls
+            .entry(TypeId::of::<Tr>())
+            .or_insert_with(|| Box::new(Vec::<TraitImpl<Tr>>::new()))
+            .downcast_mut::<Vec<TraitImpl<Tr>>>()
+            .unwrap();
+
+        assert!(
+            !impls
+                .iter()
+                .any(|registered| registered.component == TypeId::of::<C>()),
+            "Component {} was already registered as {}.",
+            std::any::type_name::<C>(),
+            std::any::type_name::<Tr>()
+        );
+
+        impls.push(TraitImpl {
+            component: TypeId::of::<C>(),
+            name: std::any::type_name::<C>(),
+            cast: Box::new(move |component| cast(component.downcast_ref::<C>().unwrap())),
+            cast_mut: Box::new(move |component| cast_mut(component.downcast_mut::<C>().unwrap())),
+        });
+
+        self.matches.get_mut().unwrap().remove(&TypeId::of::<Tr>());
+    }
+
+    fn impls<Tr: ?Sized + 'static>(&self) -> &[TraitImpl<Tr>] {
+        self.impls
+            .get(&TypeId::of::<Tr>())
+            .map(|impls| {
+                impls
+                    .downcast_ref::<Vec<TraitImpl<Tr>>>()
+                    .unwrap()
+                    .as_slice()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the names of the components registered as implementors of `Tr`.
+    pub fn implementors<Tr: ?Sized + 'static>(&self) -> Vec<&'static str> {
+        self.impls::<Tr>().iter().map(|imp| imp.name).collect()
+    }
+
+    /// Returns the archetype and implementor indices of every implementor of `Tr` in the archetypes.
+    /// Archetypes are never removed, so only the ones created since the last call are checked.
+    fn matches<Tr: ?Sized + 'static>(&self, archetypes: &[Archetype]) -> Arc<Vec<(usize, usize)>> {
+        let type_id = TypeId::of::<Tr>();
+
+        if let Some(matches) = self.matches.read().unwrap().get(&type_id) {
+            if matches.checked == archetypes.len() {
+                return matches.pairs.clone();
+            }
+        }
+
+        let mut cache = self.matches.write().unwrap();
+        let matches = cache.entry(type_id).or_default();
+        let impls = self.impls::<Tr>();
+        let pairs = Arc::make_mut(&mut matches.pairs);
+
+        for (archetype_idx, archetype) in archetypes.iter().enumerate().skip(matches.checked) {
+            for (impl_idx, imp) in impls.iter().enumerate() {
+                if archetype.has_component(imp.component) {
+                    pairs.push((archetype_idx, impl_idx));
+                }
+            }
+        }
+
+        matches.checked = archetypes.len();
+        matches.pairs.clone()
+    }
+
+    /// Returns every registered implementor of `Tr` in the archetypes, once per component.
+    pub fn query<'world, Tr: ?Sized + 'static>(
+        &'world self,
+        archetypes: &'world [Archetype],
+    ) -> impl Iterator<Item = (EntityId, &'world Tr)> + 'world {
+        let impls = self.impls::<Tr>();
+        let matches = self.matches::<Tr>(archetypes);
+
+        (0..matches.len()).flat_map(move |idx| {
+            let (archetype_idx, impl_idx) = matches[idx];
+            let archetype = &archetypes[archetype_idx];
+            let imp = &impls[impl_idx];
+            let column = archetype.column_dyn(imp.component).unwrap();
+
+            archetype
+                .entity_ids()
+                .iter()
+                .enumerate()
+                .map(move |(idx, id)| (*id, (imp.cast)(column.get(idx).unwrap())))
+        })
+    }
+
+    /// Runs `f` for every registered implementor of `Tr` in the archetypes, once per component.
+    pub fn for_each_mut<Tr: ?Sized + 'static>(
+        &self,
+        archetypes: &mut [Archetype],
+        mut f: impl FnMut(EntityId, &mut Tr),
+    ) {
+        let impls = self.impls::<Tr>();
+
+        for &(archetype_idx, impl_idx) in self.matches::<Tr>(archetypes).iter() {
+            let imp = &impls[impl_idx];
+            let (ids, column) = archetypes[archetype_idx]
+                .column_dyn_mut(imp.component)
+                .unwrap();
+
+            for (idx, id) in ids.iter().enumerate() {
+                f(*id, (imp.cast_mut)(column.get_mut(idx).unwrap()));
+            }
+        }
+    }
+}
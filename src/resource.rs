@@ -0,0 +1,91 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    thread::{self, ThreadId},
+};
+
+/// Resources which aren't `Send` or `Sync`, pinned to the thread which created the `World`.
+/// Every access checks the current thread, so the store can be moved along with the world.
+pub(crate) struct NonSendResources {
+    owner: ThreadId,
+    resources: HashMap<TypeId, Box<dyn Any>>,
+}
+
+// The resources are only accessed, and dropped, on the owner thread.
+unsafe impl Send for NonSendResources {}
+unsafe impl Sync for NonSendResources {}
+
+impl NonSendResources {
+    pub fn new() -> Self {
+        Self {
+            owner: thread::current().id(),
+            resources: HashMap::new(),
+        }
+    }
+
+    #[inline]
+    pub fn is_owner_thread(&self) -> bool {
+        thread::current().id() == self.owner
+    }
+
+    fn check_thread<T>(&self) {
+        assert!(
+            self.is_owner_thread(),
+            "Non-send resource {} cannot be accessed from a thread other than the one which created the world.",
+            std::any::type_name::<T>()
+        );
+    }
+
+    pub fn insert<T: 'static>(&mut self, resource: T) {
+        self.check_thread::<T>();
+        self.resources.insert(TypeId::of::<T>(), Box::new(resource));
+    }
+
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.check_thread::<T>();
+        self.resources
+            .get(&TypeId::of::<T>())
+            .map(|resource| resource.downcast_ref::<T>().unwrap())
+    }
+
+    pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.check_thread::<T>();
+        self.resources
+            .get_mut(&TypeId::of::<T>())
+            .map(|resource| resource.downcast_mut::<T>().unwrap())
+    }
+
+    pub fn remove<T: 'static>(&mut self) -> Option<T> {
+        self.check_thread::<T>();
+        self.resources
+            .remove(&TypeId::of::<T>())
+            .map(|resource| *resource.downcast::<T>().unwrap())
+    }
+
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.check_thread::<T>();
+        self.resources.contains_key(&TypeId::of::<T>())
+    }
+}
+
+impl Default for NonSendResources {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for NonSendResources {
+    fn drop(&mut self) {
+        if self.resources.is_empty() || self.is_owner_thread() {
+            return;
+        }
+
+        // Leaked rather than dropped on the wrong thread. Moving a world to another thread is valid,
+        // so this only warns instead of panicking.
+        std::mem::forget(std::mem::take(&mut self.resources));
+
+        eprintln!(
+            "Non-send resources were leaked, as they were dropped on a thread other than the one which created the world."
+        );
+    }
+}
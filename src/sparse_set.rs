@@ -1,25 +1,28 @@
-use crate::entities::EntityId;
+use std::{iter::Zip, ops::Range, slice, vec};
 
+use crate::{component::move_to, entities::EntityId};
+
+/// Stores the IDs and values in separate vectors, so the IDs can be borrowed as a slice.
 pub(crate) struct SparseSet<T> {
-    dense: Vec<(EntityId, T)>,
+    ids: Vec<EntityId>,
+    values: Vec<T>,
     sparse: Vec<usize>,
-    len: usize,
 }
 
 impl<T> SparseSet<T> {
     pub fn new() -> Self {
         Self {
-            dense: Vec::new(),
+            ids: Vec::new(),
+            values: Vec::new(),
             sparse: Vec::new(),
-            len: 0,
         }
     }
 
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
-            dense: Vec::with_capacity(capacity),
+            ids: Vec::with_capacity(capacity),
+            values: Vec::with_capacity(capacity),
             sparse: Vec::with_capacity(capacity),
-            len: 0,
         }
     }
 
@@ -29,7 +32,7 @@ impl<T> SparseSet<T> {
         }
 
         let idx = self.sparse[*id];
-        if idx >= self.len || self.dense[idx].0 != id {
+        if idx >= self.ids.len() || self.ids[idx] != id {
             return None;
         }
 
@@ -38,7 +41,7 @@ impl<T> SparseSet<T> {
 
     pub fn insert(&mut self, id: EntityId, value: T) {
         if let Some(index) = self.dense_idx(id) {
-            self.dense[index].1 = value;
+            self.values[index] = value;
             return;
         }
 
@@ -46,43 +49,81 @@ impl<T> SparseSet<T> {
             self.sparse.resize(*id + 1, 0);
         }
 
-        self.sparse[*id] = self.len;
-        self.dense.push((id, value));
-        self.len += 1;
+        self.sparse[*id] = self.ids.len();
+        self.ids.push(id);
+        self.values.push(value);
     }
 
     pub fn remove(&mut self, id: EntityId) -> Option<T> {
-        if let Some(index) = self.dense_idx(id) {
-            let entry = self.dense.swap_remove(index);
-            self.len -= 1;
+        let index = self.dense_idx(id)?;
+
+        self.ids.swap_remove(index);
+        let value = self.values.swap_remove(index);
+
+        if index < self.ids.len() {
+            let swapped_key = *self.ids[index];
+            self.sparse[swapped_key] = index;
+        }
+
+        Some(value)
+    }
+
+    /// Removes the entry of the given ID, keeping the order of the others.
+    pub fn remove_ordered(&mut self, id: EntityId) -> Option<T> {
+        let index = self.dense_idx(id)?;
+
+        self.ids.remove(index);
+        let value = self.values.remove(index);
+        self.reindex(index..self.ids.len());
+
+        Some(value)
+    }
 
-            if index < self.len {
-                let swapped_key = *self.dense[index].0;
-                self.sparse[swapped_key] = index;
-            }
+    /// Moves the entry at dense index `from` to `to`, shifting the ones in between.
+    pub fn move_to(&mut self, from: usize, to: usize) {
+        move_to(&mut self.ids, from, to);
+        move_to(&mut self.values, from, to);
+        self.reindex(from.min(to)..from.max(to) + 1);
+    }
 
-            Some(entry.1)
-        } else {
-            None
+    fn reindex(&mut self, range: Range<usize>) {
+        for index in range {
+            self.sparse[*self.ids[index]] = index;
         }
     }
 
+    /// Swaps the entries at the given dense indices.
+    pub fn swap(&mut self, a: usize, b: usize) {
+        self.ids.swap(a, b);
+        self.values.swap(a, b);
+
+        self.sparse[*self.ids[a]] = a;
+        self.sparse[*self.ids[b]] = b;
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Returns the IDs in dense order.
+    #[inline]
+    pub fn ids(&self) -> &[EntityId] {
+        &self.ids
+    }
+
     pub fn contains(&self, id: EntityId) -> bool {
         self.dense_idx(id).is_some()
     }
 
-    /*pub fn get(&self, key: usize) -> Option<&T> {
-        match self.dense_idx(key) {
-            Some(index) => Some(&self.dense[index].1),
-            None => None,
-        }
-    }*/
+    pub fn get(&self, id: EntityId) -> Option<&T> {
+        let index = self.dense_idx(id)?;
+        Some(&self.values[index])
+    }
 
     pub fn get_mut(&mut self, id: EntityId) -> Option<&mut T> {
-        match self.dense_idx(id) {
-            Some(index) => Some(&mut self.dense[index].1),
-            None => None,
-        }
+        let index = self.dense_idx(id)?;
+        Some(&mut self.values[index])
     }
 
     pub fn get_or_insert_with(&mut self, id: EntityId, f: impl FnOnce() -> T) -> &mut T {
@@ -94,7 +135,7 @@ impl<T> SparseSet<T> {
     }
 
     pub fn iter(&self) -> Iter<'_, T> {
-        self.dense.iter()
+        self.ids.iter().zip(self.values.iter())
     }
 }
 
@@ -104,16 +145,16 @@ impl<T> Default for SparseSet<T> {
     }
 }
 
-pub type Iter<'a, T> = std::slice::Iter<'a, (EntityId, T)>;
+pub type Iter<'a, T> = Zip<slice::Iter<'a, EntityId>, slice::Iter<'a, T>>;
 
-pub type IntoIter<T> = std::vec::IntoIter<(EntityId, T)>;
+pub type IntoIter<T> = Zip<vec::IntoIter<EntityId>, vec::IntoIter<T>>;
 
 impl<T> IntoIterator for SparseSet<T> {
     type Item = (EntityId, T);
     type IntoIter = IntoIter<T>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.dense.into_iter()
+        self.ids.into_iter().zip(self.values)
     }
 }
 
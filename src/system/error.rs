@@ -0,0 +1,112 @@
+use std::{borrow::Cow, error::Error, fmt};
+
+/// A type-erased error returned by a fallible `System`.
+pub type BoxedError = Box<dyn Error + Send + Sync>;
+
+pub type SystemResult = Result<(), BoxedError>;
+
+/// Return types allowed for function systems, either `()` or a `Result`.
+pub trait IntoSystemResult {
+    fn into_result(self) -> SystemResult;
+}
+
+impl IntoSystemResult for () {
+    #[inline]
+    fn into_result(self) -> SystemResult {
+        Ok(())
+    }
+}
+
+impl<E: Into<BoxedError>> IntoSystemResult for Result<(), E> {
+    #[inline]
+    fn into_result(self) -> SystemResult {
+        self.map_err(Into::into)
+    }
+}
+
+/// The error reported when a `System` panics while the `Engine` is catching panics.
+#[derive(Debug)]
+pub struct SystemPanic(pub String);
+
+impl fmt::Display for SystemPanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "panicked: {}", self.0)
+    }
+}
+
+impl Error for SystemPanic {}
+
+/// An error returned by a `System`, along with the name of the system that failed.
+#[derive(Debug)]
+pub struct SystemError {
+    system: Cow<'static, str>,
+    error: BoxedError,
+}
+
+impl SystemError {
+    pub fn new(system: Cow<'static, str>, error: BoxedError) -> Self {
+        Self { system, error }
+    }
+
+    #[inline]
+    pub fn system(&self) -> &str {
+        &self.system
+    }
+
+    #[inline]
+    pub fn error(&self) -> &(dyn Error + Send + Sync + 'static) {
+        self.error.as_ref()
+    }
+
+    /// Returns true if the error was caused by the system panicking.
+    #[inline]
+    pub fn is_panic(&self) -> bool {
+        self.error.is::<SystemPanic>()
+    }
+
+    pub fn into_error(self) -> BoxedError {
+        self.error
+    }
+}
+
+impl fmt::Display for SystemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "System {} failed: {}", self.system, self.error)
+    }
+}
+
+impl Error for SystemError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.error.as_ref())
+    }
+}
+
+/// What the `Engine` should do after a `System` fails.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ErrorAction {
+    /// Keep running the remaining systems.
+    Continue,
+    /// Keep running the remaining systems, but never run the failed system again.
+    Disable,
+    /// Skip the remaining systems for this run.
+    Abort,
+}
+
+/// Prints the error to stderr and continues running.
+/// This is the default error handler of an `Engine`.
+pub fn log_error(error: &SystemError) -> ErrorAction {
+    eprintln!("{error}");
+    ErrorAction::Continue
+}
+
+/// Prints the error to stderr and disables the failed system.
+pub fn disable_on_error(error: &SystemError) -> ErrorAction {
+    eprintln!("{error}");
+    ErrorAction::Disable
+}
+
+/// Prints the error to stderr and aborts the current run.
+pub fn abort_on_error(error: &SystemError) -> ErrorAction {
+    eprintln!("{error}");
+    ErrorAction::Abort
+}
@@ -0,0 +1,95 @@
+use std::{borrow::Cow, marker::PhantomData};
+
+use lemon_ecs_macros::all_tuples;
+
+use crate::world::World;
+
+use super::{IntoSystem, IntoSystemResult, System, SystemParam, SystemResult};
+
+/// A function taking a mutable reference to a `World` followed by one or more `SystemParam`s.
+/// Functions without parameters implement `System` directly.
+/// The `Marker` parameter is the function signature, allowing implementations for every arity.
+pub trait SystemParamFunction<Marker>: Send + 'static {
+    type Param: SystemParam;
+    type Output: IntoSystemResult;
+
+    fn call(
+        &mut self,
+        world: &mut World,
+        param: <Self::Param as SystemParam>::Item<'_>,
+    ) -> Self::Output;
+}
+
+macro_rules! impl_system_param_function {
+    ($($t:ident),*) => {
+        impl<F, R, $($t),*> SystemParamFunction<fn($($t,)*) -> R> for F
+        where
+            F: Send
+                + 'static
+                + FnMut(&mut World, $($t),*) -> R
+                + FnMut(&mut World, $($t::Item<'_>),*) -> R,
+            R: IntoSystemResult,
+            $($t: SystemParam),*
+        {
+            type Param = ($($t,)*);
+            type Output = R;
+
+            #[allow(non_snake_case)]
+            fn call(
+                &mut self,
+                world: &mut World,
+                param: <Self::Param as SystemParam>::Item<'_>,
+            ) -> Self::Output {
+                // Calling through a generic function pins down which `FnMut` implementation is used.
+                #[allow(clippy::too_many_arguments)]
+                fn call_inner<R, $($t),*>(
+                    mut f: impl FnMut(&mut World, $($t),*) -> R,
+                    world: &mut World,
+                    $($t: $t),*
+                ) -> R {
+                    f(world, $($t),*)
+                }
+
+                let ($($t,)*) = param;
+                call_inner(self, world, $($t),*)
+            }
+        }
+    };
+}
+
+all_tuples!(impl_system_param_function, 1..13);
+
+/// A `System` created from a `SystemParamFunction`, owning the state of its parameters.
+pub struct FunctionSystem<F: SystemParamFunction<Marker>, Marker> {
+    func: F,
+    state: <F::Param as SystemParam>::State,
+    _marker: PhantomData<fn() -> Marker>,
+}
+
+impl<F: SystemParamFunction<Marker>, Marker: 'static> System for FunctionSystem<F, Marker> {
+    fn run(&mut self, world: &mut World) -> SystemResult {
+        world.increment_change_tick();
+
+        let param = F::Param::fetch(&mut self.state, world);
+        self.func.call(world, param).into_result()
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        std::any::type_name::<F>().into()
+    }
+}
+
+/// Marks `IntoSystem` implementations for functions.
+pub struct IsFunctionSystem;
+
+impl<F: SystemParamFunction<Marker>, Marker: 'static> IntoSystem<(IsFunctionSystem, Marker)> for F {
+    type System = FunctionSystem<F, Marker>;
+
+    fn into_system(self) -> Self::System {
+        FunctionSystem {
+            func: self,
+            state: Default::default(),
+            _marker: PhantomData,
+        }
+    }
+}
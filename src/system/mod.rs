@@ -0,0 +1,62 @@
+use std::borrow::Cow;
+
+use crate::world::World;
+
+mod error;
+mod function;
+mod param;
+
+pub use error::*;
+pub use function::*;
+pub use param::*;
+
+/// Systems must be `Send`, so an `Engine` can be moved to a worker thread.
+pub trait System: Send {
+    fn run(&mut self, world: &mut World) -> SystemResult;
+
+    /// Returns the name of the system, used when reporting errors.
+    fn name(&self) -> Cow<'static, str> {
+        std::any::type_name::<Self>().into()
+    }
+}
+
+/// Functions taking only a mutable reference to a `World`, returning either nothing or a `Result`.
+impl<F, R> System for F
+where
+    F: FnMut(&mut World) -> R + Send,
+    R: IntoSystemResult,
+{
+    fn run(&mut self, world: &mut World) -> SystemResult {
+        world.increment_change_tick();
+        self(world).into_result()
+    }
+}
+
+impl System for Vec<Box<dyn System>> {
+    /// Runs every system in order, stopping at the first error.
+    #[inline]
+    fn run(&mut self, world: &mut World) -> SystemResult {
+        for system in self.iter_mut() {
+            system.run(world)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Conversion into a `System`.
+/// The `Marker` parameter only exists to allow implementations for different function signatures.
+pub trait IntoSystem<Marker> {
+    type System: System + 'static;
+
+    fn into_system(self) -> Self::System;
+}
+
+impl<S: System + 'static> IntoSystem<()> for S {
+    type System = S;
+
+    #[inline]
+    fn into_system(self) -> Self::System {
+        self
+    }
+}
@@ -0,0 +1,111 @@
+use std::ops::{Deref, DerefMut};
+
+use lemon_ecs_macros::all_tuples;
+
+use crate::world::World;
+
+/// A parameter of a function system, fetched from state owned by that system.
+pub trait SystemParam {
+    /// State kept by the system between runs.
+    type State: Default + Send + 'static;
+
+    /// The value passed to the system function.
+    type Item<'s>;
+
+    /// Fetches the parameter before the system runs.
+    fn fetch<'s>(state: &'s mut Self::State, world: &World) -> Self::Item<'s>;
+}
+
+macro_rules! impl_tuple_param {
+    ($($t:ident),*) => {
+        impl<$($t: SystemParam),*> SystemParam for ($($t,)*) {
+            type State = ($($t::State,)*);
+            type Item<'s> = ($($t::Item<'s>,)*);
+
+            #[allow(non_snake_case, clippy::unused_unit)]
+            fn fetch<'s>(state: &'s mut Self::State, _world: &World) -> Self::Item<'s> {
+                let ($($t,)*) = state;
+                ($($t::fetch($t, _world),)*)
+            }
+        }
+    };
+}
+
+// `Default` is only implemented for tuples of up to 12 elements.
+all_tuples!(impl_tuple_param, 0..13);
+
+/// State local to a single system, created with `Default` and kept across runs.
+/// Two systems created from the same function do not share their `Local`s.
+pub struct Local<'s, T: Default + Send + 'static>(&'s mut T);
+
+impl<'a, T: Default + Send + 'static> SystemParam for Local<'a, T> {
+    type State = T;
+    type Item<'s> = Local<'s, T>;
+
+    #[inline]
+    fn fetch<'s>(state: &'s mut Self::State, _world: &World) -> Self::Item<'s> {
+        Local(state)
+    }
+}
+
+impl<'s, T: Default + Send + 'static> Deref for Local<'s, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl<'s, T: Default + Send + 'static> DerefMut for Local<'s, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0
+    }
+}
+
+/// The `World` change ticks seen by a single system.
+/// The change tick advances every time a function system runs, and is stamped on component changes,
+/// so `is_newer` tells whether an `EntitySnapshot` changed since the previous run.
+#[derive(Clone, Copy, Debug)]
+pub struct SystemTicks {
+    last_run: Option<u64>,
+    this_run: u64,
+}
+
+impl SystemTicks {
+    /// Returns the change tick of the previous run of this system, if there was one.
+    #[inline]
+    pub fn last_run(&self) -> Option<u64> {
+        self.last_run
+    }
+
+    /// Returns the change tick of the current run.
+    #[inline]
+    pub fn this_run(&self) -> u64 {
+        self.this_run
+    }
+
+    #[inline]
+    pub fn is_first_run(&self) -> bool {
+        self.last_run.is_none()
+    }
+
+    /// Returns true if the given tick happened after the previous run of this system.
+    #[inline]
+    pub fn is_newer(&self, tick: u64) -> bool {
+        self.last_run.is_none_or(|last_run| tick > last_run)
+    }
+}
+
+impl SystemParam for SystemTicks {
+    type State = Option<u64>;
+    type Item<'s> = SystemTicks;
+
+    fn fetch<'s>(state: &'s mut Self::State, world: &World) -> Self::Item<'s> {
+        let this_run = world.change_tick();
+
+        SystemTicks {
+            last_run: state.replace(this_run),
+            this_run,
+        }
+    }
+}
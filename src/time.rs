@@ -0,0 +1,315 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// A monotonic source of time used by the `Engine` to advance `Time` and `FixedTime`.
+pub trait Clock: Send + Sync {
+    /// Returns the time elapsed since the clock was created.
+    fn now(&self) -> Duration;
+
+    /// Waits for the given duration to pass, used for frame pacing.
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// A `Clock` backed by `std::time::Instant`.
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    #[inline]
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// A `Clock` that only advances when told to.
+/// Clones share the same time, so a handle can be kept to drive an `Engine` deterministically.
+#[derive(Clone, Default)]
+pub struct ManualClock(Arc<Mutex<Duration>>);
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves the clock forward by the given duration.
+    pub fn advance(&self, duration: Duration) {
+        *self.0.lock().unwrap() += duration;
+    }
+
+    /// Sets the current time of the clock, panicking if it is earlier than the current one.
+    pub fn set(&self, now: Duration) {
+        let mut current = self.0.lock().unwrap();
+        assert!(
+            now >= *current,
+            "ManualClock cannot be set to an earlier time."
+        );
+        *current = now;
+    }
+}
+
+impl Clock for ManualClock {
+    #[inline]
+    fn now(&self) -> Duration {
+        *self.0.lock().unwrap()
+    }
+
+    /// Advances the clock instead of blocking.
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
+
+/// A resource tracking the time between `Engine` runs.
+/// Virtual time follows real time, but can be paused or scaled.
+pub struct Time {
+    last_update: Option<Duration>,
+    real_delta: Duration,
+    real_elapsed: Duration,
+    delta: Duration,
+    elapsed: Duration,
+    ticks: u64,
+    paused: bool,
+    relative_speed: f64,
+}
+
+impl Time {
+    pub fn new() -> Self {
+        Self {
+            last_update: None,
+            real_delta: Duration::ZERO,
+            real_elapsed: Duration::ZERO,
+            delta: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            ticks: 0,
+            paused: false,
+            relative_speed: 1.0,
+        }
+    }
+
+    /// Returns the virtual time elapsed during the last run.
+    #[inline]
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// Returns the virtual time elapsed during the last run, in seconds.
+    #[inline]
+    pub fn delta_secs(&self) -> f64 {
+        self.delta.as_secs_f64()
+    }
+
+    /// Returns the total virtual time elapsed.
+    #[inline]
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns the total virtual time elapsed, in seconds.
+    #[inline]
+    pub fn elapsed_secs(&self) -> f64 {
+        self.elapsed.as_secs_f64()
+    }
+
+    /// Returns the real time elapsed during the last run, ignoring pausing and scaling.
+    #[inline]
+    pub fn real_delta(&self) -> Duration {
+        self.real_delta
+    }
+
+    /// Returns the total real time elapsed, ignoring pausing and scaling.
+    #[inline]
+    pub fn real_elapsed(&self) -> Duration {
+        self.real_elapsed
+    }
+
+    /// Returns the number of times the `Engine` has run.
+    #[inline]
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Stops virtual time from advancing until `unpause` is called.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn unpause(&mut self) {
+        self.paused = false;
+    }
+
+    #[inline]
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Returns the rate at which virtual time advances relative to real time.
+    #[inline]
+    pub fn relative_speed(&self) -> f64 {
+        self.relative_speed
+    }
+
+    /// Sets the rate at which virtual time advances relative to real time.
+    /// Panics if the speed is negative or not finite.
+    pub fn set_relative_speed(&mut self, speed: f64) {
+        assert!(
+            speed.is_finite() && speed >= 0.0,
+            "Relative speed must be finite and non-negative."
+        );
+
+        self.relative_speed = speed;
+    }
+
+    /// Advances the time using the given clock reading.
+    pub(crate) fn update(&mut self, now: Duration) {
+        let real_delta = match self.last_update {
+            Some(last) => now.saturating_sub(last),
+            None => Duration::ZERO,
+        };
+
+        self.last_update = Some(now);
+        self.real_delta = real_delta;
+        self.real_elapsed += real_delta;
+
+        self.delta = if self.paused {
+            Duration::ZERO
+        } else {
+            real_delta.mul_f64(self.relative_speed)
+        };
+
+        self.elapsed += self.delta;
+        self.ticks += 1;
+    }
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A resource controlling the fixed-update schedule of an `Engine`.
+/// Virtual time is accumulated every run and spent in steps of `timestep`.
+pub struct FixedTime {
+    timestep: Duration,
+    accumulator: Duration,
+    elapsed: Duration,
+    ticks: u64,
+    max_steps: u32,
+    steps: u32,
+}
+
+impl FixedTime {
+    pub fn new(timestep: Duration) -> Self {
+        assert!(!timestep.is_zero(), "Fixed timestep must be non-zero.");
+
+        Self {
+            timestep,
+            accumulator: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            ticks: 0,
+            max_steps: 8,
+            steps: 0,
+        }
+    }
+
+    /// Returns the duration of a single fixed step.
+    #[inline]
+    pub fn timestep(&self) -> Duration {
+        self.timestep
+    }
+
+    /// Sets the duration of a single fixed step.
+    /// Panics if the timestep is zero.
+    pub fn set_timestep(&mut self, timestep: Duration) {
+        assert!(!timestep.is_zero(), "Fixed timestep must be non-zero.");
+        self.timestep = timestep;
+    }
+
+    /// Returns the accumulated time that has not yet been spent on a step.
+    #[inline]
+    pub fn accumulator(&self) -> Duration {
+        self.accumulator
+    }
+
+    /// Returns how far the accumulator is into the next step, from 0 to 1.
+    /// Useful for interpolating between fixed steps.
+    #[inline]
+    pub fn overstep_fraction(&self) -> f64 {
+        self.accumulator.as_secs_f64() / self.timestep.as_secs_f64()
+    }
+
+    /// Returns the total time spent on fixed steps.
+    #[inline]
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns the number of fixed steps that have run.
+    #[inline]
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Returns the maximum number of fixed steps during a single run.
+    #[inline]
+    pub fn max_steps(&self) -> u32 {
+        self.max_steps
+    }
+
+    /// Sets the maximum number of fixed steps during a single run.
+    /// Time left over after the last step is discarded, so a slow run can't snowball.
+    pub fn set_max_steps(&mut self, max_steps: u32) {
+        self.max_steps = max_steps;
+    }
+
+    /// Adds the given virtual time to the accumulator.
+    pub(crate) fn accumulate(&mut self, delta: Duration) {
+        self.accumulator += delta;
+        self.steps = 0;
+    }
+
+    /// Spends a single step from the accumulator, returning false if there isn't enough time.
+    pub(crate) fn expend(&mut self) -> bool {
+        if self.accumulator < self.timestep {
+            return false;
+        }
+
+        if self.steps >= self.max_steps {
+            self.accumulator = Duration::from_nanos(
+                (self.accumulator.as_nanos() % self.timestep.as_nanos()) as u64,
+            );
+            return false;
+        }
+
+        self.accumulator -= self.timestep;
+        self.elapsed += self.timestep;
+        self.ticks += 1;
+        self.steps += 1;
+        true
+    }
+}
+
+impl Default for FixedTime {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(1) / 60)
+    }
+}
@@ -1,14 +1,22 @@
 use std::{
     any::{Any, TypeId},
     collections::HashMap,
+    fmt::Display,
 };
 
 use crate::{
-    buffer::WorldBuffer,
+    buffer::{ParallelBuffer, WorldBuffer},
     changes::{ChangeDetection, ChangeRecord},
-    component::{Bundle, Component, TypeBundle},
-    entities::{Archetypes, EntityId, EntityIter, IdGenerator},
-    query::{Query, QueryChanged, QueryRetriever, QuerySelector},
+    component::{
+        Bundle, Component, ComponentHooks, ComponentRegistry, HookKind, HookRegistry, TypeBundle,
+    },
+    entities::{Archetypes, EntityId, EntityIter, IdGenerator, IdReserver, SortOrder},
+    event::{panic_unregistered, EventWriter, Events},
+    query::{
+        DynamicFilter, DynamicQuery, ObservedChanges, Query, QueryChanged, QueryMut,
+        QueryObservers, QueryRetriever, QuerySelector, TraitRegistry,
+    },
+    resource::NonSendResources,
 };
 
 pub enum ComponentChange {
@@ -16,34 +24,125 @@ pub enum ComponentChange {
     Remove(TypeId),
 }
 
-#[derive(Default)]
+/// Returned by `spawn_at` when an entity can't be spawned with the given ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnError {
+    /// The ID belongs to a spawned or reserved entity.
+    InUse(EntityId),
+    /// The ID is larger than `EntityId::MAX`, or too far past the IDs handed out so far,
+    /// see `World::set_max_id_gap`.
+    OutOfRange(EntityId),
+}
+
+impl Display for SpawnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpawnError::InUse(id) => write!(f, "entity {} is already in use", **id),
+            SpawnError::OutOfRange(id) => write!(f, "entity {} is out of range", **id),
+        }
+    }
+}
+
+impl std::error::Error for SpawnError {}
+
 pub struct World {
     entities: IdGenerator,
     archetypes: Archetypes,
-    resources: HashMap<TypeId, Box<dyn Any>>,
+    resources: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+    non_send: NonSendResources,
     changes: ChangeDetection,
     despawned: Vec<EntityId>,
+    hooks: HookRegistry,
+    observers: QueryObservers,
+    traits: TraitRegistry,
 }
 
 impl World {
     pub fn spawn(&mut self, bundle: impl Bundle) -> EntityId {
         let id = self.entities.spawn();
+        self.spawn_with_id(id, bundle);
+        id
+    }
+
+    /// Reserves an entity ID which can be spawned later with `spawn_reserved`.
+    #[inline]
+    pub fn reserve_entity(&self) -> EntityId {
+        self.entities.reserve()
+    }
+
+    /// Spawns an entity with a reserved ID, panicking if the ID wasn't reserved.
+    pub fn spawn_reserved(&mut self, id: EntityId, bundle: impl Bundle) {
+        assert!(
+            self.entities.claim(id),
+            "Entity {} was not reserved, or has already been spawned.",
+            *id
+        );
+
+        self.spawn_with_id(id, bundle);
+    }
+
+    /// Spawns an entity with a specific ID, failing if the ID is already spawned or reserved, or out of range.
+    /// Used to replay entities spawned by another `World`.
+    pub fn spawn_at(&mut self, id: EntityId, bundle: impl Bundle) -> Result<(), SpawnError> {
+        self.entities.take(id)?;
+        self.spawn_with_id(id, bundle);
+        Ok(())
+    }
+
+    /// Spawns an entity with a reserved ID, failing if the ID wasn't reserved.
+    pub(crate) fn try_spawn_reserved(
+        &mut self,
+        id: EntityId,
+        bundle: impl Bundle,
+    ) -> Result<(), SpawnError> {
+        if !self.entities.claim(id) {
+            return Err(SpawnError::InUse(id));
+        }
+
+        self.spawn_with_id(id, bundle);
+        Ok(())
+    }
+
+    /// Sets how far past the next fresh ID `spawn_at` can spawn an entity, 65536 by default.
+    /// Storage grows to the largest spawned ID, so this bounds the memory an untrusted log can make it allocate.
+    #[inline]
+    pub fn set_max_id_gap(&mut self, max_gap: usize) {
+        self.entities.set_max_gap(max_gap);
+    }
+
+    fn spawn_with_id(&mut self, id: EntityId, bundle: impl Bundle) {
         let components = bundle.components();
 
         for component in &components {
             self.changes.mark_added(id, (*component).as_any().type_id());
+            self.hooks.trigger(HookKind::Add, id, component.as_ref());
+            self.hooks.trigger(HookKind::Insert, id, component.as_ref());
         }
 
-        self.archetypes
-            .component_archetype(&components)
-            .insert(id, components);
-        id
+        let idx = self.archetypes.component_archetype_idx(&components);
+
+        self.observers.transition(
+            id,
+            None,
+            Some(self.archetypes.get(idx)),
+            self.hooks.buffer_mut(),
+        );
+
+        self.archetypes.get_mut(idx).insert(id, components);
+
+        self.flush_hooks();
     }
 
     pub fn despawn(&mut self, id: EntityId) {
         if let Some(archetype) = self.archetypes.entity_archetype_mut(id) {
             if let Some(components) = archetype.remove(id) {
+                self.observers
+                    .transition(id, Some(archetype), None, self.hooks.buffer_mut());
+
                 for component in components {
+                    self.hooks
+                        .trigger(HookKind::Replace, id, component.as_ref());
+                    self.hooks.trigger(HookKind::Remove, id, component.as_ref());
                     self.changes.mark_removed(id, component);
                 }
 
@@ -56,6 +155,8 @@ impl World {
                 self.entities.despawn(id);
             }
         }
+
+        self.flush_hooks();
     }
 
     pub fn has_component<T: 'static + Component>(&self, id: EntityId) -> bool {
@@ -66,8 +167,13 @@ impl World {
     }
 
     #[inline]
-    fn modify_bundle<T>(&mut self, id: EntityId, bundle: Vec<Box<dyn Component>>, changes: T)
-    where
+    fn modify_bundle<T>(
+        &mut self,
+        id: EntityId,
+        old_idx: usize,
+        bundle: Vec<Box<dyn Component>>,
+        changes: T,
+    ) where
         T: Iterator<Item = ComponentChange>,
     {
         let mut components: HashMap<TypeId, Box<dyn Component>> = bundle
@@ -81,15 +187,24 @@ impl World {
                     let type_id = (*component).as_any().type_id();
 
                     if let Some(changed) = components.insert(type_id, component) {
+                        self.hooks.trigger(HookKind::Replace, id, changed.as_ref());
                         self.changes.mark_changed(id, changed);
                     } else {
                         self.changes.mark_added(id, type_id);
+                        self.hooks
+                            .trigger(HookKind::Add, id, components[&type_id].as_ref());
                     }
+
+                    self.hooks
+                        .trigger(HookKind::Insert, id, components[&type_id].as_ref());
                 }
                 ComponentChange::Remove(type_id) => {
                     let removed = components.remove(&type_id);
 
                     if let Some(component) = removed {
+                        self.hooks
+                            .trigger(HookKind::Replace, id, component.as_ref());
+                        self.hooks.trigger(HookKind::Remove, id, component.as_ref());
                         self.changes.mark_removed(id, component);
                     }
                 }
@@ -97,20 +212,25 @@ impl World {
         }
 
         let components: Vec<_> = components.into_values().collect();
+        let new_idx = self.archetypes.component_archetype_idx(&components);
 
-        self.archetypes
-            .component_archetype(&components)
-            .insert(id, components);
+        self.observers.transition(
+            id,
+            Some(self.archetypes.get(old_idx)),
+            Some(self.archetypes.get(new_idx)),
+            self.hooks.buffer_mut(),
+        );
+
+        self.archetypes.get_mut(new_idx).insert(id, components);
     }
 
     pub fn modify(&mut self, id: EntityId, changes: impl IntoIterator<Item = ComponentChange>) {
-        let archetype = self.archetypes.entity_archetype_mut(id);
-
-        if archetype.is_none() {
-            return;
-        }
+        let idx = match self.archetypes.entity_archetype_idx(id) {
+            Some(idx) => idx,
+            None => return,
+        };
 
-        let archetype = archetype.unwrap();
+        let archetype = self.archetypes.get_mut(idx);
 
         let changes = &mut changes.into_iter();
         let mut consumed = None;
@@ -123,6 +243,13 @@ impl World {
                     if archetype.has_component(type_id) {
                         let changed = archetype.replace_component(id, component);
 
+                        self.hooks.trigger(HookKind::Replace, id, changed.as_ref());
+                        self.hooks.trigger(
+                            HookKind::Insert,
+                            id,
+                            archetype.get_component_dyn(id, type_id).unwrap(),
+                        );
+
                         self.changes.mark_changed(id, changed);
                     } else {
                         consumed = Some(ComponentChange::Insert(component));
@@ -140,7 +267,48 @@ impl World {
 
         if let Some(consumed) = consumed {
             let bundle = archetype.remove(id).unwrap();
-            self.modify_bundle(id, bundle, std::iter::once(consumed).chain(changes));
+            self.modify_bundle(id, idx, bundle, std::iter::once(consumed).chain(changes));
+        }
+
+        self.flush_hooks();
+    }
+
+    /// Returns the lifecycle hooks of the component type, which can be used to register new hooks.
+    pub fn component_hooks<T: Component>(&mut self) -> &mut ComponentHooks {
+        self.hooks.hooks_mut::<T>()
+    }
+
+    /// Returns the entities which started or stopped matching the selector since the last call.
+    /// The first call starts observing the selector, and reports every matching entity as entered.
+    pub fn take_observed<T: QuerySelector>(&mut self) -> ObservedChanges {
+        self.observers
+            .take_changes::<T>()
+            .unwrap_or_else(|| ObservedChanges {
+                entered: self.archetypes.query_ids::<T>().collect(),
+                left: Vec::new(),
+            })
+    }
+
+    /// Registers a callback run whenever an entity starts matching the selector.
+    pub fn on_enter<T: QuerySelector>(
+        &mut self,
+        callback: impl Fn(EntityId, &mut WorldBuffer) + Send + Sync + 'static,
+    ) {
+        self.observers.on_enter::<T>(Box::new(callback));
+    }
+
+    /// Registers a callback run whenever an entity stops matching the selector.
+    pub fn on_leave<T: QuerySelector>(
+        &mut self,
+        callback: impl Fn(EntityId, &mut WorldBuffer) + Send + Sync + 'static,
+    ) {
+        self.observers.on_leave::<T>(Box::new(callback));
+    }
+
+    /// Applies the changes recorded by hooks, including the ones recorded while applying them.
+    fn flush_hooks(&mut self) {
+        while let Some(buffer) = self.hooks.take_buffer() {
+            buffer.apply_world(self);
         }
     }
 
@@ -148,7 +316,7 @@ impl World {
         let changes = components
             .components()
             .into_iter()
-            .map(|component| ComponentChange::Insert(component));
+            .map(ComponentChange::Insert);
 
         self.modify(id, changes);
     }
@@ -163,14 +331,105 @@ impl World {
             .and_then(|archetype| archetype.get_component::<T>(id))
     }
 
-    pub fn query<T>(&mut self) -> Query<T>
+    /// Queries the world through a shared reference, so several threads can query it at once.
+    pub fn query<T>(&self) -> Query<'_, T>
     where
         T: 'static + QueryRetriever,
     {
         Query::new(self)
     }
 
-    pub fn query_selector<T>(&mut self) -> EntityIter
+    /// Queries the world with exclusive access, so components can be borrowed mutably.
+    /// Changes made through mutable borrows are not recorded by change detection.
+    pub fn query_mut<T>(&mut self) -> QueryMut<'_, T>
+    where
+        T: 'static + QuerySelector,
+    {
+        QueryMut::new(self)
+    }
+
+    /// Queries entities having every component type in `with` and none in `without`.
+    /// The matching archetypes are cached by the sets of types, like typed queries.
+    pub fn query_dynamic(&self, with: &[TypeId], without: &[TypeId]) -> DynamicQuery<'_> {
+        let indices = self
+            .archetypes
+            .dynamic_index_list(DynamicFilter::new(with, without));
+
+        DynamicQuery::new(self.archetypes.as_slice(), indices, with)
+    }
+
+    /// Queries entities by the names of components in the registry, see `ComponentRegistry::type_id_by_name`.
+    /// Returns `None` if any of the names are unknown.
+    pub fn query_dynamic_by_name(
+        &self,
+        registry: &ComponentRegistry,
+        with: &[&str],
+        without: &[&str],
+    ) -> Option<DynamicQuery<'_>> {
+        let type_ids = |names: &[&str]| -> Option<Vec<TypeId>> {
+            names
+                .iter()
+                .map(|name| registry.type_id_by_name(name))
+                .collect()
+        };
+
+        Some(self.query_dynamic(&type_ids(with)?, &type_ids(without)?))
+    }
+
+    /// Stably sorts the storage of every archetype with the component `C` by the key, once.
+    /// Entities inserted later are not sorted, see `keep_sorted_by_key`. Archetypes which keep another order
+    /// are sorted by it again afterwards, so this key only orders entities which are equal in the kept one.
+    pub fn sort_by_key<C: Component, K: Ord>(
+        &mut self,
+        key: impl Fn(&C) -> K + Send + Sync + 'static,
+    ) {
+        self.archetypes.sort(&SortOrder::by_key(key));
+    }
+
+    /// Keeps the storage of every archetype with the component `C` sorted by the key, so queries return its
+    /// entities in order. An archetype with several kept components follows the one registered first.
+    /// Changes made through mutable borrows are not tracked, so the order must then be restored with `sort_by_key`.
+    pub fn keep_sorted_by_key<C: Component, K: Ord>(
+        &mut self,
+        key: impl Fn(&C) -> K + Send + Sync + 'static,
+    ) {
+        self.archetypes.keep_sorted(SortOrder::by_key(key));
+    }
+
+    /// Registers the component `C` as an implementor of the trait object type `Tr`, such as `dyn Damageable`.
+    /// The casts are usually `|component| component`. Panics if `C` was already registered for `Tr`.
+    pub fn register_trait<Tr, C>(
+        &mut self,
+        cast: fn(&C) -> &Tr,
+        cast_mut: fn(&mut C) -> &mut Tr,
+    ) -> &mut Self
+    where
+        Tr: ?Sized + 'static,
+        C: Component,
+    {
+        self.traits.register(cast, cast_mut);
+        self
+    }
+
+    /// Returns the names of the components registered as implementors of `Tr`.
+    pub fn trait_implementors<Tr: ?Sized + 'static>(&self) -> Vec<&'static str> {
+        self.traits.implementors::<Tr>()
+    }
+
+    /// Queries every component registered as an implementor of `Tr` through `register_trait`.
+    /// An entity with several implementors is returned once for each of them.
+    pub fn query_trait<Tr: ?Sized + 'static>(&self) -> impl Iterator<Item = (EntityId, &Tr)> {
+        self.traits.query::<Tr>(self.archetypes.as_slice())
+    }
+
+    /// Runs `f` for every component registered as an implementor of `Tr`, borrowing it mutably.
+    /// Changes made through mutable borrows are not recorded by change detection.
+    pub fn for_each_trait_mut<Tr: ?Sized + 'static>(&mut self, f: impl FnMut(EntityId, &mut Tr)) {
+        self.traits
+            .for_each_mut::<Tr>(self.archetypes.as_mut_slice(), f);
+    }
+
+    pub fn query_selector<T>(&self) -> EntityIter<'_>
     where
         T: 'static + QuerySelector,
     {
@@ -179,11 +438,10 @@ impl World {
 
     /// Returns a query for all entities that have changed since the last call.
     /// Entities will not be despawned until their changes have been processed.
-    pub fn query_changed<T: 'static + Component>(&mut self) -> QueryChanged<T> {
-        let record = self
-            .changes
-            .consume_record::<T>()
-            .unwrap_or_else(|| ChangeRecord::from_ids::<T>(self.archetypes.query_ids::<T>()));
+    pub fn query_changed<T: 'static + Component>(&mut self) -> QueryChanged<'_, T> {
+        let record = self.changes.consume_record::<T>().unwrap_or_else(|| {
+            ChangeRecord::from_ids::<T>(self.archetypes.query_ids::<T>(), self.changes.tick())
+        });
 
         QueryChanged::new(self, record)
     }
@@ -200,16 +458,164 @@ impl World {
             .map(|resource| resource.downcast_mut::<T>().unwrap())
     }
 
-    pub fn insert_resource<T: 'static>(&mut self, resource: T) {
+    pub fn insert_resource<T: 'static + Send + Sync>(&mut self, resource: T) {
         self.resources.insert(TypeId::of::<T>(), Box::new(resource));
     }
 
-    pub(crate) fn insert_resource_boxed(&mut self, resource: Box<dyn Any>) {
+    /// Inserts the default value of a resource if it doesn't exist yet, and returns it.
+    pub fn init_resource<T: 'static + Send + Sync + Default>(&mut self) -> &mut T {
+        self.resources
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(T::default()))
+            .downcast_mut::<T>()
+            .unwrap()
+    }
+
+    #[inline]
+    pub fn contains_resource<T: 'static>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<T>())
+    }
+
+    pub fn remove_resource<T: 'static>(&mut self) -> Option<T> {
+        self.resources
+            .remove(&TypeId::of::<T>())
+            .map(|resource| *resource.downcast::<T>().unwrap())
+    }
+
+    #[inline]
+    pub(crate) fn remove_resource_by_id(&mut self, type_id: TypeId) {
+        self.resources.remove(&type_id);
+    }
+
+    /// Temporarily removes a resource so it can be used mutably alongside the rest of the world.
+    /// Panics if the resource doesn't exist. A resource of the same type inserted inside the scope is overwritten.
+    pub fn resource_scope<T: 'static + Send + Sync, R>(
+        &mut self,
+        f: impl FnOnce(&mut World, &mut T) -> R,
+    ) -> R {
+        let mut resource = self.remove_resource::<T>().unwrap_or_else(|| {
+            panic!(
+                "Resource {} does not exist in the world.",
+                std::any::type_name::<T>()
+            )
+        });
+
+        let result = f(self, &mut resource);
+        self.insert_resource(resource);
+        result
+    }
+
+    pub(crate) fn insert_resource_boxed(&mut self, resource: Box<dyn Any + Send + Sync>) {
         self.resources.insert((*resource).type_id(), resource);
     }
 
+    /// Inserts a resource which isn't `Send` or `Sync`.
+    /// Non-send resources can only be accessed from the thread which created the world, and panic otherwise.
+    pub fn insert_non_send<T: 'static>(&mut self, resource: T) {
+        self.non_send.insert(resource);
+    }
+
+    pub fn get_non_send<T: 'static>(&self) -> Option<&T> {
+        self.non_send.get::<T>()
+    }
+
+    pub fn get_non_send_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.non_send.get_mut::<T>()
+    }
+
+    pub fn remove_non_send<T: 'static>(&mut self) -> Option<T> {
+        self.non_send.remove::<T>()
+    }
+
+    pub fn contains_non_send<T: 'static>(&self) -> bool {
+        self.non_send.contains::<T>()
+    }
+
+    /// Returns true if non-send resources can be accessed from the current thread.
+    #[inline]
+    pub fn is_owner_thread(&self) -> bool {
+        self.non_send.is_owner_thread()
+    }
+
+    /// Inserts an empty `Events<E>` resource if one doesn't exist yet.
+    /// Events added this way are not updated automatically, see `Engine::add_event`.
+    pub fn add_event<E: 'static + Send + Sync>(&mut self) {
+        if self.get_resource::<Events<E>>().is_none() {
+            self.insert_resource(Events::<E>::new());
+        }
+    }
+
+    /// Sends an event, panicking if its type hasn't been added.
+    pub fn send_event<E: 'static>(&mut self, event: E) {
+        self.event_writer::<E>().send(event);
+    }
+
+    /// Returns a writer for events of type `E`, panicking if the type hasn't been added.
+    pub fn event_writer<E: 'static>(&mut self) -> EventWriter<'_, E> {
+        let events = self
+            .get_resource_mut::<Events<E>>()
+            .unwrap_or_else(|| panic_unregistered::<E>());
+
+        EventWriter::new(events)
+    }
+
+    /// Returns the current change tick, which advances every time a function system runs.
+    #[inline]
+    pub fn change_tick(&self) -> u64 {
+        self.changes.tick()
+    }
+
+    /// Advances the change tick, returning the new value.
+    pub(crate) fn increment_change_tick(&mut self) -> u64 {
+        self.changes.increment_tick()
+    }
+
+    /// Returns an empty buffer whose spawned entities get their IDs reserved immediately.
+    /// IDs reserved by a buffer which is dropped without being applied are released, so they can be reused.
+    pub fn buffer(&self) -> WorldBuffer {
+        WorldBuffer::with_ids(self.entities.reserver().clone())
+    }
+
+    /// Returns a buffer which threads can record into concurrently, reserving IDs like `buffer`.
+    pub fn parallel_buffer(&self) -> ParallelBuffer {
+        ParallelBuffer::with_ids(self.entities.reserver().clone())
+    }
+
+    #[inline]
+    pub(crate) fn archetypes(&self) -> &Archetypes {
+        &self.archetypes
+    }
+
+    #[inline]
+    pub(crate) fn archetypes_mut(&mut self) -> &mut Archetypes {
+        &mut self.archetypes
+    }
+
+    #[inline]
+    pub(crate) fn id_reserver(&self) -> &IdReserver {
+        self.entities.reserver()
+    }
+
     #[inline]
     pub fn apply_buffer(&mut self, buffer: WorldBuffer) {
         buffer.apply_world(self);
     }
 }
+
+impl Default for World {
+    fn default() -> Self {
+        let entities = IdGenerator::new();
+
+        Self {
+            hooks: HookRegistry::new(WorldBuffer::with_ids(entities.reserver().clone())),
+            entities,
+            archetypes: Archetypes::default(),
+            resources: HashMap::new(),
+            non_send: NonSendResources::new(),
+            changes: ChangeDetection::default(),
+            despawned: Vec::new(),
+            observers: QueryObservers::default(),
+            traits: TraitRegistry::default(),
+        }
+    }
+}
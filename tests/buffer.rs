@@ -0,0 +1,225 @@
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc,
+};
+
+use lemon_ecs::{buffer::WorldBuffer, entities::EntityId, macros::Component, world::World};
+
+mod common;
+use common::components::{Position, Velocity};
+
+#[derive(Component, Debug, PartialEq, Eq)]
+struct Target(EntityId);
+
+#[test]
+pub fn buffered_spawn_reserves_id() {
+    let mut world = World::default();
+    let shooter = world.spawn(Position(0, 0));
+
+    let mut buffer = world.buffer();
+    let projectile = buffer.spawn(Velocity(1, 0)).id().unwrap();
+    buffer.insert(shooter, Target(projectile));
+
+    let other = world.spawn(Position(5, 5));
+    assert_ne!(other, projectile, "Reserved ID should not be reused");
+
+    world.apply_buffer(buffer);
+
+    assert_eq!(
+        world.get_component::<Velocity>(projectile),
+        Some(&Velocity(1, 0)),
+        "Projectile should be spawned with the reserved ID"
+    );
+    assert_eq!(
+        world.get_component::<Target>(shooter),
+        Some(&Target(projectile)),
+        "Shooter should target the projectile"
+    );
+}
+
+#[test]
+pub fn unconnected_buffer_has_no_id() {
+    let mut world = World::default();
+
+    let mut buffer = WorldBuffer::new();
+    assert!(
+        buffer.spawn(Position(1, 1)).id().is_none(),
+        "Buffer without a world should not reserve IDs"
+    );
+
+    world.apply_buffer(buffer);
+    assert_eq!(
+        world.query::<Position>().into_iter().count(),
+        1,
+        "Entity should be spawned"
+    );
+}
+
+#[test]
+#[should_panic(expected = "cannot be applied to a World other than the one which created it")]
+pub fn buffer_from_other_world() {
+    let first = World::default();
+    let mut second = World::default();
+
+    let mut buffer = first.buffer();
+    buffer.spawn(Position(1, 1));
+
+    second.apply_buffer(buffer);
+}
+
+#[test]
+pub fn dropped_buffer_releases_ids() {
+    let mut world = World::default();
+
+    let mut buffer = world.buffer();
+    let reserved = buffer.spawn(Position(1, 1)).id().unwrap();
+    drop(buffer);
+
+    assert_eq!(
+        world.spawn(Position(2, 2)),
+        reserved,
+        "ID of an unapplied spawn should be reused"
+    );
+}
+
+#[test]
+pub fn commands_applied_in_order() {
+    let mut world = World::default();
+    let entity = world.spawn(Position(0, 0));
+
+    let mut buffer = world.buffer();
+    buffer
+        .insert(entity, Velocity(1, 1))
+        .remove::<Velocity>()
+        .insert(Velocity(2, 2));
+    buffer.remove::<Position>(entity);
+    buffer.insert(entity, Position(3, 3));
+
+    world.apply_buffer(buffer);
+
+    assert_eq!(
+        world.get_component::<Velocity>(entity),
+        Some(&Velocity(2, 2)),
+        "Last inserted velocity should be kept"
+    );
+    assert_eq!(
+        world.get_component::<Position>(entity),
+        Some(&Position(3, 3)),
+        "Position should be inserted after being removed"
+    );
+}
+
+#[test]
+pub fn despawn_after_edits() {
+    let mut world = World::default();
+    let added = Arc::new(AtomicU32::new(0));
+
+    let counter = added.clone();
+    world
+        .component_hooks::<Velocity>()
+        .on_add(move |_, _: &Velocity, _| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+
+    let entity = world.spawn(Position(0, 0));
+
+    let mut buffer = world.buffer();
+    buffer.insert(entity, Velocity(1, 1));
+    buffer.despawn(entity);
+    world.apply_buffer(buffer);
+
+    assert_eq!(
+        added.load(Ordering::Relaxed),
+        1,
+        "Edits before a despawn should be applied"
+    );
+
+    let entity = world.spawn(Position(0, 0));
+
+    let mut buffer = world.buffer();
+    buffer.set_coalescing(true);
+    buffer.insert(entity, Velocity(1, 1));
+    buffer.despawn(entity);
+    world.apply_buffer(buffer);
+
+    assert_eq!(
+        added.load(Ordering::Relaxed),
+        1,
+        "Coalesced edits should be dropped on despawn"
+    );
+}
+
+#[test]
+pub fn coalescing_keeps_last_change() {
+    let mut world = World::default();
+    let first = world.spawn(Position(0, 0));
+    let second = world.spawn(Position(0, 0));
+
+    let mut buffer = world.buffer();
+    buffer.set_coalescing(true);
+    buffer.insert(first, Velocity(1, 1));
+    buffer.insert(second, Velocity(2, 2));
+    buffer.insert(first, Velocity(3, 3));
+    buffer.remove::<Position>(first);
+    buffer.remove::<Velocity>(second).insert(Position(4, 4));
+    world.apply_buffer(buffer);
+
+    assert_eq!(
+        world.get_component::<Velocity>(first),
+        Some(&Velocity(3, 3)),
+        "Last velocity should be kept"
+    );
+    assert!(
+        !world.has_component::<Position>(first),
+        "Position should be removed"
+    );
+    assert!(
+        !world.has_component::<Velocity>(second),
+        "Velocity should be removed"
+    );
+    assert_eq!(
+        world.get_component::<Position>(second),
+        Some(&Position(4, 4)),
+        "Position should be replaced"
+    );
+}
+
+#[test]
+pub fn parallel_buffers_merged_in_order() {
+    let mut world = World::default();
+    let entity = world.spawn(Position(0, 0));
+
+    let buffer = world.parallel_buffer();
+
+    std::thread::scope(|scope| {
+        for thread in (0..4).rev() {
+            let buffer = &buffer;
+
+            scope.spawn(move || {
+                let mut local = buffer.local(1, thread);
+                local.insert(entity, Position(1, thread as u32));
+            });
+        }
+
+        let buffer = &buffer;
+        scope.spawn(move || {
+            let mut local = buffer.local(0, 7);
+            local.insert(entity, Position(0, 7));
+            local.spawn(Velocity(1, 1));
+        });
+    });
+
+    let buffer = buffer.into_buffer();
+    world.apply_buffer(buffer);
+
+    assert_eq!(
+        world.get_component::<Position>(entity),
+        Some(&Position(1, 3)),
+        "Last system and thread should be applied last"
+    );
+    assert_eq!(
+        world.query::<Velocity>().into_iter().count(),
+        1,
+        "Spawn should be applied"
+    );
+}
@@ -0,0 +1,328 @@
+use std::any::TypeId;
+
+use lemon_ecs::{
+    buffer::WorldBuffer,
+    codec::{write_varint, CodecError, Decode, Encode, Reader},
+    component::ComponentRegistry,
+    entities::EntityId,
+    macros::Component,
+    world::{SpawnError, World},
+};
+
+mod common;
+use common::components::{Position, Velocity};
+
+#[derive(Component, Debug, PartialEq, Eq)]
+struct Target(EntityId);
+
+impl Encode for Position {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        (self.0, self.1).encode(bytes);
+    }
+}
+
+impl Decode for Position {
+    fn decode(reader: &mut Reader<'_>) -> Result<Self, CodecError> {
+        let (x, y) = Decode::decode(reader)?;
+        Ok(Position(x, y))
+    }
+}
+
+impl Encode for Target {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.0.encode(bytes);
+    }
+}
+
+impl Decode for Target {
+    fn decode(reader: &mut Reader<'_>) -> Result<Self, CodecError> {
+        EntityId::decode(reader).map(Target)
+    }
+}
+
+mod physics {
+    use lemon_ecs::{
+        codec::{CodecError, Decode, Encode, Reader},
+        macros::Component,
+    };
+
+    /// Shares its short name with the common `Position`.
+    #[derive(Component)]
+    pub struct Position(pub f32);
+
+    impl Encode for Position {
+        fn encode(&self, bytes: &mut Vec<u8>) {
+            self.0.encode(bytes);
+        }
+    }
+
+    impl Decode for Position {
+        fn decode(reader: &mut Reader<'_>) -> Result<Self, CodecError> {
+            f32::decode(reader).map(Position)
+        }
+    }
+}
+
+fn registry() -> ComponentRegistry {
+    let mut registry = ComponentRegistry::new();
+    registry.register::<Position>().register::<Target>();
+    registry
+}
+
+#[test]
+pub fn replay_command_log() {
+    let registry = registry();
+
+    let mut world = World::default();
+    let mut replica = World::default();
+
+    let shooter = world.spawn(Position(0, 0));
+    replica.spawn(Position(0, 0));
+
+    let mut buffer = world.buffer();
+    let projectile = buffer.spawn(Position(1, 1)).id().unwrap();
+    buffer
+        .insert(shooter, Target(projectile))
+        .remove::<Position>()
+        .insert(Position(2, 3));
+
+    let log = buffer.encode(&registry).unwrap();
+    assert_eq!(
+        log,
+        buffer.encode(&registry).unwrap(),
+        "Encoding should be deterministic"
+    );
+
+    world.apply_buffer(buffer);
+    replica.apply_buffer(WorldBuffer::decode(&log, &registry).unwrap());
+
+    for world in [&world, &replica] {
+        assert_eq!(
+            world.get_component::<Position>(projectile),
+            Some(&Position(1, 1)),
+            "Projectile should be spawned with the recorded ID"
+        );
+        assert_eq!(
+            world.get_component::<Target>(shooter),
+            Some(&Target(projectile)),
+            "Shooter should target the projectile"
+        );
+        assert_eq!(
+            world.get_component::<Position>(shooter),
+            Some(&Position(2, 3)),
+            "Shooter should be moved"
+        );
+    }
+}
+
+#[test]
+pub fn unregistered_components() {
+    let registry = registry();
+    let mut world = World::default();
+
+    let mut buffer = world.buffer();
+    buffer.spawn((Position(0, 0), Velocity(1, 1)));
+
+    assert!(
+        matches!(
+            buffer.encode(&registry),
+            Err(CodecError::UnregisteredComponent(_))
+        ),
+        "Velocity is not registered"
+    );
+
+    let other = ComponentRegistry::new();
+
+    let mut buffer = world.buffer();
+    buffer.spawn(Position(0, 0));
+    let log = buffer.encode(&registry).unwrap();
+
+    assert_eq!(
+        WorldBuffer::decode(&log, &other).err(),
+        Some(CodecError::UnknownComponent(0)),
+        "Position is not registered in the other registry"
+    );
+    assert_eq!(
+        WorldBuffer::decode(&log[..log.len() - 1], &registry).err(),
+        Some(CodecError::UnexpectedEnd),
+        "Truncated log should fail"
+    );
+
+    // The recording buffer holds the ID in reserve until it's dropped.
+    drop(buffer);
+    world.apply_buffer(WorldBuffer::decode(&log, &registry).unwrap());
+}
+
+#[test]
+pub fn query_by_registered_name() {
+    let registry = registry();
+
+    let mut world = World::default();
+    let target = world.spawn(Position(1, 2));
+    world.spawn((Position(3, 4), Target(target)));
+
+    let query = world
+        .query_dynamic_by_name(&registry, &["Position"], &["Target"])
+        .unwrap();
+
+    let entities: Vec<_> = query.into_iter().map(|entity| entity.id()).collect();
+    assert_eq!(entities, vec![target], "Only the target should match");
+
+    assert!(
+        world
+            .query_dynamic_by_name(&registry, &["Velocity"], &[])
+            .is_none(),
+        "Unregistered names should not match"
+    );
+}
+
+#[test]
+pub fn ambiguous_registered_name() {
+    let mut registry = registry();
+    registry.register::<physics::Position>();
+
+    assert_eq!(
+        registry.type_id_by_name("Position"),
+        None,
+        "Ambiguous short names should not match"
+    );
+    assert_eq!(
+        registry.type_id_by_name(std::any::type_name::<physics::Position>()),
+        Some(TypeId::of::<physics::Position>()),
+        "Full names should still match"
+    );
+    assert_eq!(
+        registry.type_id_by_name("Target"),
+        Some(TypeId::of::<Target>()),
+        "Unique short names should still match"
+    );
+}
+
+#[test]
+pub fn untrusted_entity_ids() {
+    let registry = registry();
+    let world = World::default();
+
+    let mut buffer = world.buffer();
+    let spawned = buffer.spawn(Position(1, 2)).id().unwrap();
+    let log = buffer.encode(&registry).unwrap();
+
+    let mut replica = World::default();
+    let existing = replica.spawn(Position(0, 0));
+    assert_eq!(
+        existing, spawned,
+        "Both worlds should hand out the same first ID"
+    );
+
+    assert_eq!(
+        WorldBuffer::decode(&log, &registry)
+            .unwrap()
+            .try_apply_world(&mut replica),
+        Err(SpawnError::InUse(spawned)),
+        "Spawning with a live ID should fail"
+    );
+
+    let mut log = Vec::new();
+    write_varint(1, &mut log);
+    log.push(0);
+    Some(EntityId::new(usize::MAX)).encode(&mut log);
+    write_varint(0, &mut log);
+
+    assert_eq!(
+        WorldBuffer::decode(&log, &registry).err(),
+        Some(CodecError::Invalid("entity ID")),
+        "IDs above EntityId::MAX should be rejected"
+    );
+}
+
+#[test]
+pub fn spawn_at_skips_ids() {
+    let mut world = World::default();
+    let far = EntityId::new(1000);
+
+    assert_eq!(world.spawn_at(far, Position(1, 1)), Ok(()));
+    assert_eq!(
+        world.spawn_at(far, Position(2, 2)),
+        Err(SpawnError::InUse(far)),
+        "ID should already be in use"
+    );
+    assert_eq!(
+        world.spawn_at(EntityId::new(EntityId::MAX + 1), Position(3, 3)),
+        Err(SpawnError::OutOfRange(EntityId::new(EntityId::MAX + 1)))
+    );
+
+    let skipped: Vec<_> = (0..3).map(|_| *world.spawn(Velocity(0, 0))).collect();
+    assert_eq!(
+        skipped,
+        vec![0, 1, 2],
+        "Skipped IDs should be handed out in order"
+    );
+
+    assert_eq!(world.spawn_at(EntityId::new(500), Position(4, 4)), Ok(()));
+    assert_eq!(*world.spawn(Velocity(0, 0)), 3);
+}
+
+#[test]
+pub fn untrusted_huge_entity_id() {
+    let registry = registry();
+    let huge = EntityId::new(EntityId::MAX);
+
+    let mut log = Vec::new();
+    write_varint(1, &mut log);
+    log.push(0);
+    Some(huge).encode(&mut log);
+    write_varint(0, &mut log);
+
+    let mut world = World::default();
+
+    assert_eq!(
+        WorldBuffer::decode(&log, &registry)
+            .unwrap()
+            .try_apply_world(&mut world),
+        Err(SpawnError::OutOfRange(huge)),
+        "IDs far past the next fresh ID should be rejected without allocating"
+    );
+
+    world.set_max_id_gap(10);
+    assert_eq!(
+        world.spawn_at(EntityId::new(10), Position(0, 0)),
+        Err(SpawnError::OutOfRange(EntityId::new(10)))
+    );
+    assert_eq!(world.spawn_at(EntityId::new(9), Position(0, 0)), Ok(()));
+}
+
+#[test]
+pub fn replayed_spawn_of_reserved_id() {
+    let registry = registry();
+    let mut world = World::default();
+
+    let mut buffer = world.buffer();
+    let reserved = buffer.spawn(Position(1, 2)).id().unwrap();
+
+    let mut replayed = World::default().buffer();
+    assert_eq!(
+        replayed.spawn(Position(3, 4)).id(),
+        Some(reserved),
+        "Both buffers should hand out the same first ID"
+    );
+    let log = replayed.encode(&registry).unwrap();
+
+    assert_eq!(
+        WorldBuffer::decode(&log, &registry)
+            .unwrap()
+            .try_apply_world(&mut world),
+        Err(SpawnError::InUse(reserved)),
+        "Spawning with a reserved ID should fail"
+    );
+    assert_eq!(
+        world.spawn_at(reserved, Position(5, 6)),
+        Err(SpawnError::InUse(reserved))
+    );
+
+    world.apply_buffer(buffer);
+    assert_eq!(
+        world.get_component::<Position>(reserved),
+        Some(&Position(1, 2)),
+        "The reserving buffer should still spawn its entity"
+    );
+}
@@ -1,3 +1,5 @@
+#![allow(dead_code)]
+
 use lemon_ecs::macros::{Bundle, Component};
 
 #[derive(Component, PartialEq, Eq, Debug, Clone, Copy)]
@@ -0,0 +1,80 @@
+use lemon_ecs::{
+    engine::Engine,
+    event::{EventReader, Events},
+    world::World,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+struct Hit(u32);
+
+#[derive(Default)]
+struct Seen(Vec<(&'static str, u32)>);
+
+fn writer_system(world: &mut World) {
+    let tick = world.get_resource::<Seen>().unwrap().0.len() as u32;
+
+    if tick == 0 {
+        world.send_event(Hit(1));
+        world.event_writer::<Hit>().send_batch([Hit(2), Hit(3)]);
+    }
+}
+
+fn read_hits(world: &mut World, hits: &mut EventReader<Hit>, name: &'static str) {
+    let hits: Vec<u32> = hits.read(world).map(|hit| hit.0).collect();
+
+    let seen = world.get_resource_mut::<Seen>().unwrap();
+    seen.0.extend(hits.into_iter().map(|hit| (name, hit)));
+}
+
+fn reader_before(world: &mut World, mut hits: EventReader<Hit>) {
+    read_hits(world, &mut hits, "before");
+}
+
+fn reader_after(world: &mut World, mut hits: EventReader<Hit>) {
+    read_hits(world, &mut hits, "after");
+}
+
+#[test]
+pub fn events_read_once_per_reader() {
+    let mut engine = Engine::default();
+    engine.add_event::<Hit>().insert_resource(Seen::default());
+
+    engine
+        .push_system(reader_before)
+        .push_system(writer_system)
+        .push_system(reader_after);
+
+    engine.run();
+    engine.run();
+    engine.run();
+
+    assert_eq!(
+        engine.get_resource::<Seen>().unwrap().0,
+        vec![
+            ("after", 1),
+            ("after", 2),
+            ("after", 3),
+            ("before", 1),
+            ("before", 2),
+            ("before", 3),
+        ]
+    );
+}
+
+#[test]
+pub fn events_live_two_runs() {
+    let mut engine = Engine::default();
+    engine.add_event::<Hit>();
+
+    engine.send_event(Hit(1));
+    engine.run();
+
+    engine.send_event(Hit(2));
+    assert_eq!(engine.get_resource::<Events<Hit>>().unwrap().len(), 2);
+
+    engine.run();
+    assert_eq!(engine.get_resource::<Events<Hit>>().unwrap().len(), 1);
+
+    engine.run();
+    assert!(engine.get_resource::<Events<Hit>>().unwrap().is_empty());
+}
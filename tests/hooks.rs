@@ -0,0 +1,83 @@
+use std::sync::{Arc, Mutex};
+
+use lemon_ecs::{buffer::WorldBuffer, entities::EntityId, macros::Component, world::World};
+
+mod common;
+use common::components::{Position, Velocity};
+
+#[derive(Component, Debug, PartialEq, Eq)]
+#[component(on_add = mark_alive, on_remove = mark_dead)]
+struct Health(u32);
+
+#[derive(Component, Debug, PartialEq, Eq)]
+struct Alive;
+
+fn mark_alive(id: EntityId, _health: &Health, buffer: &mut WorldBuffer) {
+    buffer.insert(id, Alive);
+}
+
+fn mark_dead(id: EntityId, _health: &Health, buffer: &mut WorldBuffer) {
+    buffer.remove::<Alive>(id);
+}
+
+#[test]
+pub fn hooks_declared_on_component() {
+    let mut world = World::default();
+
+    let entity = world.spawn((Health(10), Position(1, 2)));
+    assert!(
+        world.has_component::<Alive>(entity),
+        "Entity should be alive"
+    );
+
+    world.remove::<Health>(entity);
+    assert!(
+        !world.has_component::<Alive>(entity),
+        "Entity should be dead"
+    );
+
+    world.insert(entity, Health(5));
+    assert!(
+        world.has_component::<Alive>(entity),
+        "Entity should be alive"
+    );
+}
+
+#[test]
+pub fn hooks_registered_on_world() {
+    let mut world = World::default();
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    macro_rules! log_hook {
+        ($name:literal) => {{
+            let log = log.clone();
+            move |id: EntityId, position: &Position, _: &mut WorldBuffer| {
+                log.lock().unwrap().push(($name, *id, position.0));
+            }
+        }};
+    }
+
+    world
+        .component_hooks::<Position>()
+        .on_add(log_hook!("add"))
+        .on_insert(log_hook!("insert"))
+        .on_replace(log_hook!("replace"))
+        .on_remove(log_hook!("remove"));
+
+    let entity = world.spawn(Position(1, 2));
+    world.insert(entity, Position(3, 4));
+    world.insert(entity, Velocity(0, 0));
+    world.despawn(entity);
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec![
+            ("add", 0, 1),
+            ("insert", 0, 1),
+            ("replace", 0, 1),
+            ("insert", 0, 3),
+            ("replace", 0, 3),
+            ("remove", 0, 3),
+        ]
+    );
+}
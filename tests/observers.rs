@@ -0,0 +1,75 @@
+use std::sync::{Arc, Mutex};
+
+use lemon_ecs::{macros::Component, query::Without, world::World};
+
+mod common;
+use common::components::{Position, Velocity};
+
+#[derive(Component, Debug, PartialEq, Eq)]
+struct Frozen;
+
+type Moving = (Position, Velocity, Without<Frozen>);
+
+#[test]
+pub fn observed_changes_accumulate() {
+    let mut world = World::default();
+
+    let first = world.spawn((Position(0, 0), Velocity(1, 1)));
+
+    let observed = world.take_observed::<Moving>();
+    assert_eq!(
+        observed.entered,
+        vec![first],
+        "Existing entity should enter"
+    );
+    assert!(observed.left.is_empty(), "No entity should have left");
+
+    let second = world.spawn(Position(0, 0));
+    world.insert(second, Velocity(2, 2));
+    world.insert(first, Frozen);
+    world.insert(second, Position(5, 5));
+
+    let observed = world.take_observed::<Moving>();
+    assert_eq!(observed.entered, vec![second], "Second entity should enter");
+    assert_eq!(observed.left, vec![first], "Frozen entity should leave");
+
+    world.remove::<Frozen>(first);
+    world.despawn(second);
+
+    let observed = world.take_observed::<Moving>();
+    assert_eq!(observed.entered, vec![first], "Thawed entity should enter");
+    assert_eq!(observed.left, vec![second], "Despawned entity should leave");
+
+    let observed = world.take_observed::<Moving>();
+    assert!(
+        observed.entered.is_empty() && observed.left.is_empty(),
+        "Changes should be consumed"
+    );
+}
+
+#[test]
+pub fn observer_callbacks() {
+    let mut world = World::default();
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let enter_log = log.clone();
+    world.on_enter::<Moving>(move |id, _| enter_log.lock().unwrap().push(("enter", *id)));
+
+    let leave_log = log.clone();
+    world.on_leave::<Moving>(move |id, buffer| {
+        leave_log.lock().unwrap().push(("leave", *id));
+        buffer.remove::<Velocity>(id);
+    });
+
+    let entity = world.spawn((Position(0, 0), Velocity(1, 1)));
+    world.insert(entity, Frozen);
+
+    assert!(
+        !world.has_component::<Velocity>(entity),
+        "Buffer from the callback should be applied"
+    );
+
+    world.remove::<Frozen>(entity);
+
+    assert_eq!(*log.lock().unwrap(), vec![("enter", 0), ("leave", 0)]);
+}
@@ -0,0 +1,88 @@
+use lemon_ecs::{
+    engine::Engine,
+    plugin::{Plugin, PluginId},
+    world::World,
+};
+
+#[derive(Default)]
+struct Log(Vec<&'static str>);
+
+fn log(world: &mut World, message: &'static str) {
+    world.get_resource_mut::<Log>().unwrap().0.push(message);
+}
+
+struct LogPlugin;
+
+impl Plugin for LogPlugin {
+    fn build(&self, engine: &mut Engine) {
+        engine.insert_resource(Log::default());
+    }
+}
+
+struct PhysicsPlugin {
+    gravity: i32,
+}
+
+impl Plugin for PhysicsPlugin {
+    fn build(&self, engine: &mut Engine) {
+        let gravity = self.gravity;
+
+        engine.push_system(move |world: &mut World| {
+            log(world, if gravity < 0 { "falling" } else { "rising" })
+        });
+    }
+
+    fn dependencies(&self) -> Vec<PluginId> {
+        vec![PluginId::of::<LogPlugin>()]
+    }
+}
+
+struct CounterPlugin;
+
+impl Plugin for CounterPlugin {
+    fn build(&self, engine: &mut Engine) {
+        engine.push_system(|world: &mut World| log(world, "count"));
+    }
+
+    fn is_unique(&self) -> bool {
+        false
+    }
+}
+
+#[test]
+pub fn plugin_group() {
+    let mut engine = Engine::default();
+
+    engine.add_plugins((
+        LogPlugin,
+        PhysicsPlugin { gravity: -10 },
+        CounterPlugin,
+        CounterPlugin,
+    ));
+
+    assert!(
+        engine.has_plugin::<PhysicsPlugin>(),
+        "Physics should be added"
+    );
+
+    engine.run();
+
+    assert_eq!(
+        engine.get_resource::<Log>().unwrap().0,
+        vec!["falling", "count", "count"]
+    );
+}
+
+#[test]
+#[should_panic(expected = "was already added")]
+pub fn plugin_duplicate() {
+    let mut engine = Engine::default();
+    engine.add_plugin(LogPlugin).add_plugin(LogPlugin);
+}
+
+#[test]
+#[should_panic(expected = "to be added first")]
+pub fn plugin_missing_dependency() {
+    let mut engine = Engine::default();
+    engine.add_plugins((PhysicsPlugin { gravity: 10 }, LogPlugin));
+}
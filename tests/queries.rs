@@ -1,7 +1,17 @@
-use lemon_ecs::{query::Without, world::World};
+use std::{
+    any::TypeId,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use lemon_ecs::{
+    entities::EntityId,
+    macros::Component,
+    query::{Mut, QuerySingleError, QueryState, Without},
+    world::World,
+};
 
 mod common;
-use common::components::{Position, Velocity};
+use common::components::{Name, Position, Velocity};
 
 #[test]
 pub fn query_no_filters() {
@@ -172,3 +182,643 @@ pub fn query_changed_modified() {
 
     assert!(query.next().is_none(), "Query should be empty");
 }
+
+#[test]
+pub fn par_for_each() {
+    let mut world = World::default();
+
+    for i in 0..1000 {
+        world.spawn(Position(i, 0));
+
+        if i % 3 == 0 {
+            world.spawn((Position(i, 0), Velocity(1, 1)));
+        }
+    }
+
+    let sum = AtomicU32::new(0);
+    world.query::<Position>().par_for_each(|position| {
+        sum.fetch_add(position.0, Ordering::Relaxed);
+    });
+
+    let expected: u32 = world.query::<Position>().into_iter().map(|p| p.0).sum();
+    assert_eq!(sum.into_inner(), expected, "Every entity should be visited");
+
+    let count = AtomicU32::new(0);
+    world
+        .query::<Position>()
+        .par_iter()
+        .filter::<Without<Velocity>>()
+        .batch_size(7)
+        .threads(3)
+        .for_each(|_| {
+            count.fetch_add(1, Ordering::Relaxed);
+        });
+
+    assert_eq!(
+        count.into_inner(),
+        1000,
+        "Filtered entities should be skipped"
+    );
+}
+
+#[test]
+pub fn par_for_each_deterministic() {
+    #[derive(Debug, PartialEq, Eq)]
+    struct Last(EntityId);
+
+    let mut world = World::default();
+
+    for i in 0..500 {
+        world.spawn(Position(i, 0));
+    }
+
+    let last = world.query::<EntityId>().into_iter().last().unwrap();
+
+    for threads in [1, 2, 5] {
+        let buffer = world.parallel_buffer();
+
+        world
+            .query::<(EntityId, Position)>()
+            .par_iter()
+            .batch_size(16)
+            .threads(threads)
+            .deterministic(true)
+            .for_each_with_buffer(&buffer, 0, |local, (id, _)| {
+                local.insert_resource(Last(id));
+            });
+
+        buffer.apply_world(&mut world);
+
+        assert_eq!(
+            world.get_resource::<Last>(),
+            Some(&Last(last)),
+            "Buffers should merge in sequential order"
+        );
+    }
+}
+
+#[test]
+pub fn chunk_iteration() {
+    let mut world = World::default();
+
+    for i in 0..10 {
+        world.spawn((Position(i, i), Velocity(1, 2)));
+    }
+
+    let frozen = world.spawn((Position(100, 100), Velocity(5, 5), Name("Frozen".into())));
+
+    for (positions, velocities, ()) in world
+        .query_mut::<(Mut<Position>, Velocity, Without<Name>)>()
+        .chunks()
+    {
+        for (position, velocity) in positions.iter_mut().zip(velocities) {
+            position.0 += velocity.0;
+            position.1 += velocity.1;
+        }
+    }
+
+    let chunks: Vec<_> = world.query::<(EntityId, Position)>().chunks().collect();
+    assert_eq!(chunks.len(), 2, "Each archetype should be a chunk");
+
+    for (ids, positions) in chunks {
+        assert_eq!(ids.len(), positions.len(), "Columns should line up");
+
+        for (id, position) in ids.iter().zip(positions) {
+            if *id == frozen {
+                assert_eq!(position, &Position(100, 100), "Filtered out");
+            } else {
+                assert_eq!(position.1, position.0 + 1, "Velocity should be applied");
+            }
+        }
+    }
+}
+
+#[test]
+#[should_panic]
+pub fn chunk_conflicting_access() {
+    let mut world = World::default();
+    world.spawn(Position(1, 2));
+
+    for _ in world.query_mut::<(Mut<Position>, Position)>().chunks() {}
+}
+
+#[test]
+pub fn query_get_and_contains() {
+    let mut world = World::default();
+
+    let entity_a = world.spawn((Position(1, 2), Velocity(3, 4)));
+    let entity_b = world.spawn(Position(5, 6));
+
+    let query = world.query::<(Position, Velocity)>();
+    assert!(query.contains(entity_a), "Entity a should match");
+    assert!(!query.contains(entity_b), "Entity b should not match");
+    assert_eq!(
+        query.get(entity_a),
+        Some((&Position(1, 2), &Velocity(3, 4)))
+    );
+    assert!(query.get(entity_b).is_none(), "Entity b should not match");
+
+    let positions: Vec<_> = world
+        .query::<(Position, Without<Velocity>)>()
+        .iter_many([entity_a, entity_b])
+        .collect();
+
+    assert_eq!(
+        positions,
+        vec![(&Position(5, 6), ())],
+        "Only entity b should match"
+    );
+
+    *world
+        .query_mut::<Mut<Position>>()
+        .get_mut(entity_b)
+        .unwrap() = Position(7, 8);
+
+    assert_eq!(
+        world.query::<Position>().get(entity_b),
+        Some(&Position(7, 8)),
+        "Position should be modified"
+    );
+}
+
+#[test]
+pub fn query_single() {
+    let mut world = World::default();
+
+    assert_eq!(
+        world.query::<Position>().get_single(),
+        Err(QuerySingleError::NoEntities)
+    );
+
+    world.spawn(Position(1, 2));
+    assert_eq!(world.query::<Position>().single(), &Position(1, 2));
+
+    world.query_mut::<Mut<Position>>().single_mut().0 = 3;
+    assert_eq!(world.query::<Position>().single(), &Position(3, 2));
+
+    world.spawn(Position(3, 4));
+    assert_eq!(
+        world.query::<Position>().get_single(),
+        Err(QuerySingleError::MultipleEntities)
+    );
+}
+
+#[test]
+pub fn query_len() {
+    let mut world = World::default();
+
+    assert!(
+        world.query::<Position>().is_empty(),
+        "Query should be empty"
+    );
+
+    for i in 0..10 {
+        world.spawn(Position(i, 0));
+    }
+
+    let entity = world.spawn((Position(10, 0), Velocity(1, 1)));
+    world.spawn(Velocity(1, 1));
+
+    assert_eq!(
+        world.query::<Position>().len(),
+        11,
+        "Query should have 11 entities"
+    );
+    assert_eq!(world.query::<(Position, Velocity)>().len(), 1);
+    assert!(
+        !world.query::<Velocity>().is_empty(),
+        "Query should not be empty"
+    );
+
+    world.despawn(entity);
+    assert!(
+        world.query::<(Position, Velocity)>().is_empty(),
+        "Empty archetypes should not count"
+    );
+
+    let mut iter = world.query::<Position>().into_iter();
+    assert_eq!(iter.len(), 10, "Iterator should know its length");
+
+    iter.nth(3);
+    assert_eq!(iter.size_hint(), (6, Some(6)), "Size hint should shrink");
+
+    let filtered = world.query::<Position>().filter::<Without<Velocity>>();
+    assert_eq!(
+        filtered.len(),
+        10,
+        "Filtered iterator should know its length"
+    );
+}
+
+#[test]
+pub fn query_state() {
+    #[derive(Component)]
+    struct Player;
+
+    let mut world = World::default();
+    world.spawn((Position(1, 1), Velocity(1, 2)));
+
+    let mut state = QueryState::<(Mut<Position>, Velocity), Without<Name>>::new(&world);
+
+    world.spawn((Position(2, 2), Velocity(1, 2), Name("Frozen".into())));
+    world.spawn((Position(3, 3), Velocity(1, 2), Player));
+    assert_eq!(state.len(&world), 2, "New archetypes should be matched");
+
+    for (position, velocity) in state.iter_mut(&mut world) {
+        position.0 += velocity.0;
+        position.1 += velocity.1;
+    }
+
+    let mut positions: Vec<_> = QueryState::<Position>::new(&world)
+        .iter(&world)
+        .cloned()
+        .collect();
+
+    positions.sort_by_key(|position| (position.0, position.1));
+
+    assert_eq!(
+        positions,
+        vec![Position(2, 2), Position(2, 3), Position(4, 5)],
+        "Only unfiltered entities should move"
+    );
+}
+
+#[test]
+#[should_panic]
+pub fn query_state_other_world() {
+    let world_a = World::default();
+    let world_b = World::default();
+
+    let mut state = QueryState::<Position>::new(&world_a);
+    state.len(&world_b);
+}
+
+#[test]
+pub fn query_dynamic() {
+    let mut world = World::default();
+
+    let entity_a = world.spawn((Position(1, 2), Velocity(3, 4)));
+    world.spawn((Position(5, 6), Velocity(7, 8), Name("Frozen".into())));
+    world.spawn(Position(9, 10));
+
+    let with = [TypeId::of::<Velocity>(), TypeId::of::<Position>()];
+    let without = [TypeId::of::<Name>()];
+
+    let query = world.query_dynamic(&with, &without);
+    assert_eq!(query.len(), 1, "Only entity a should match");
+
+    let entities: Vec<_> = query.into_iter().collect();
+    assert_eq!(entities[0].id(), entity_a, "Entity should be entity_a");
+
+    let velocity = entities[0].get(0).unwrap().downcast_ref::<Velocity>();
+    let position = entities[0].get(1).unwrap().downcast_ref::<Position>();
+    assert_eq!(
+        velocity,
+        Some(&Velocity(3, 4)),
+        "Components follow the queried order"
+    );
+    assert_eq!(
+        position,
+        Some(&Position(1, 2)),
+        "Components follow the queried order"
+    );
+
+    #[derive(Component)]
+    struct Marker;
+
+    world.spawn((Position(11, 12), Velocity(1, 1), Marker));
+
+    let chunks: Vec<_> = world.query_dynamic(&with, &without).chunks().collect();
+    assert_eq!(chunks.len(), 2, "New archetypes should be matched");
+    assert_eq!(
+        chunks[1].columns()[1].get(0).unwrap().downcast_ref(),
+        Some(&Position(11, 12))
+    );
+}
+
+#[test]
+pub fn query_trait_objects() {
+    trait Damageable {
+        fn health(&self) -> u32;
+        fn damage(&mut self, amount: u32);
+    }
+
+    #[derive(Component)]
+    struct Health(u32);
+
+    #[derive(Component)]
+    struct Shield(u32);
+
+    impl Damageable for Health {
+        fn health(&self) -> u32 {
+            self.0
+        }
+
+        fn damage(&mut self, amount: u32) {
+            self.0 = self.0.saturating_sub(amount);
+        }
+    }
+
+    impl Damageable for Shield {
+        fn health(&self) -> u32 {
+            self.0 * 2
+        }
+
+        fn damage(&mut self, amount: u32) {
+            self.0 = self.0.saturating_sub(amount / 2);
+        }
+    }
+
+    let mut world = World::default();
+
+    world
+        .register_trait::<dyn Damageable, Health>(|health| health, |health| health)
+        .register_trait::<dyn Damageable, Shield>(|shield| shield, |shield| shield);
+
+    let player = world.spawn((Position(0, 0), Health(100), Shield(10)));
+    let wall = world.spawn(Shield(50));
+    world.spawn(Position(1, 1));
+
+    assert_eq!(world.trait_implementors::<dyn Damageable>().len(), 2);
+
+    world.for_each_trait_mut::<dyn Damageable>(|_, damageable| damageable.damage(10));
+
+    let health: Vec<_> = world
+        .query_trait::<dyn Damageable>()
+        .map(|(id, damageable)| (id, damageable.health()))
+        .collect();
+
+    assert_eq!(
+        health,
+        vec![(player, 90), (player, 10), (wall, 90)],
+        "Every implementor should be returned"
+    );
+}
+
+#[test]
+pub fn query_trait_cached_archetypes() {
+    let mut world = World::default();
+
+    world.register_trait::<dyn std::fmt::Debug, Position>(|p| p, |p| p);
+
+    let first = world.spawn(Position(1, 2));
+    assert_eq!(world.query_trait::<dyn std::fmt::Debug>().count(), 1);
+
+    let second = world.spawn((Position(3, 4), Velocity(1, 1)));
+    let ids: Vec<_> = world
+        .query_trait::<dyn std::fmt::Debug>()
+        .map(|(id, _)| id)
+        .collect();
+    assert_eq!(ids, vec![first, second], "New archetypes should be matched");
+
+    world.register_trait::<dyn std::fmt::Debug, Velocity>(|v| v, |v| v);
+
+    let mut ids = Vec::new();
+    world.for_each_trait_mut::<dyn std::fmt::Debug>(|id, _| ids.push(id));
+    assert_eq!(
+        ids,
+        vec![first, second, second],
+        "New implementors should be matched"
+    );
+}
+
+#[test]
+#[should_panic(expected = "was already registered as")]
+pub fn query_trait_registered_twice() {
+    let mut world = World::default();
+
+    world
+        .register_trait::<dyn std::fmt::Debug, Position>(|p| p, |p| p)
+        .register_trait::<dyn std::fmt::Debug, Position>(|p| p, |p| p);
+}
+
+#[test]
+pub fn query_sorted_iteration() {
+    let mut world = World::default();
+
+    let entities: Vec<_> = (0..10)
+        .map(|i| world.spawn(Position((i * 7) % 10, i)))
+        .collect();
+
+    // Moving entities to another archetype changes the unsorted order.
+    world.insert(entities[2], Velocity(0, 0));
+    world.insert(entities[5], Velocity(0, 0));
+
+    let ids: Vec<_> = world.query::<EntityId>().iter_by_id().collect();
+    assert_eq!(ids, entities, "Entities should be ordered by ID");
+
+    let keys: Vec<_> = world
+        .query::<Position>()
+        .iter_sorted_by_key(|position| position.0)
+        .map(|position| position.0)
+        .collect();
+
+    assert_eq!(
+        keys,
+        (0..10).collect::<Vec<_>>(),
+        "Positions should be sorted"
+    );
+}
+
+#[test]
+pub fn archetype_sorted_storage() {
+    let mut world = World::default();
+
+    for i in 0..50 {
+        world.spawn(Position((i * 37) % 50, i));
+    }
+
+    world.sort_by_key(|position: &Position| position.0);
+
+    let keys: Vec<_> = world.query::<Position>().into_iter().map(|p| p.0).collect();
+    assert_eq!(
+        keys,
+        (0..50).collect::<Vec<_>>(),
+        "Storage should be sorted"
+    );
+
+    world.keep_sorted_by_key(|position: &Position| std::cmp::Reverse(position.1));
+
+    let (removed, _) = world
+        .query::<(EntityId, Position)>()
+        .into_iter()
+        .find(|(_, position)| position.1 == 10)
+        .unwrap();
+
+    world.despawn(removed);
+    world.spawn(Position(0, 100));
+    world.spawn((Position(0, 25), Velocity(1, 1)));
+
+    let moved = world.spawn(Position(0, 200));
+    world.insert(moved, Position(0, 30));
+
+    let keys: Vec<_> = world.query::<Position>().into_iter().map(|p| p.1).collect();
+
+    let mut expected: Vec<_> = (0..50).filter(|i| *i != 10).chain([100, 30]).collect();
+    expected.sort_by_key(|key| std::cmp::Reverse(*key));
+    expected.push(25);
+
+    assert_eq!(keys, expected, "Storage should stay sorted");
+}
+
+#[test]
+pub fn archetype_sort_keeps_kept_order() {
+    let mut world = World::default();
+
+    world.keep_sorted_by_key(|position: &Position| position.0);
+
+    for i in (0..20).rev() {
+        world.spawn(Position(i / 2, i));
+    }
+
+    world.sort_by_key(|position: &Position| std::cmp::Reverse(position.1));
+
+    let positions: Vec<_> = world
+        .query::<Position>()
+        .into_iter()
+        .map(|p| (p.0, p.1))
+        .collect();
+
+    let expected: Vec<_> = (0..10).flat_map(|i| [(i, i * 2 + 1), (i, i * 2)]).collect();
+    assert_eq!(
+        positions, expected,
+        "Kept order should be reapplied, with the sort key breaking ties"
+    );
+
+    world.spawn(Position(4, 100));
+    world.spawn(Position(0, 200));
+
+    let positions: Vec<_> = world
+        .query::<Position>()
+        .into_iter()
+        .map(|p| (p.0, p.1))
+        .collect();
+
+    assert_eq!(
+        positions[2],
+        (0, 200),
+        "Inserted entity should be settled after equal keys"
+    );
+    assert_eq!(
+        positions[11],
+        (4, 100),
+        "Inserted entity should be settled after equal keys"
+    );
+    assert!(
+        positions.windows(2).all(|pair| pair[0].0 <= pair[1].0),
+        "Storage should stay sorted after a sort"
+    );
+}
+
+#[test]
+pub fn query_where_predicates() {
+    let mut world = World::default();
+
+    let entity_a = world.spawn((Position(1, 2), Velocity(0, 0)));
+    let entity_b = world.spawn((Position(5, 6), Velocity(3, 0)));
+    world.spawn((Position(7, 8), Velocity(4, 0), Name("Frozen".into())));
+    world.spawn(Position(9, 9));
+
+    let fast: Vec<_> = world
+        .query::<(EntityId, Without<Name>)>()
+        .where_(|velocity: &Velocity| velocity.0 > 0)
+        .into_iter()
+        .map(|(id, ())| id)
+        .collect();
+
+    assert_eq!(fast, vec![entity_b], "Only entity b should match");
+
+    let limit = 6;
+    let query = world
+        .query::<Position>()
+        .where_(|position: &Position| position.0 < limit)
+        .where_(|velocity: &Velocity| velocity.0 == 0);
+
+    assert_eq!(query.count(), 1, "Predicates should compose");
+    assert!(query.contains(entity_a), "Entity a should match");
+    assert!(!query.contains(entity_b), "Entity b should not match");
+    assert_eq!(query.get(entity_a), Some(&Position(1, 2)));
+
+    assert!(
+        world
+            .query::<Position>()
+            .where_(|position: &Position| position.1 > 100)
+            .is_empty(),
+        "No entity should match"
+    );
+}
+
+#[test]
+pub fn query_where_mut_chunks_and_state() {
+    let mut world = World::default();
+
+    let slow = world.spawn((Position(1, 2), Velocity(0, 0)));
+    let fast = world.spawn((Position(5, 6), Velocity(3, 0)));
+    let named = world.spawn((Position(7, 8), Velocity(4, 0), Name("Named".into())));
+    let still = world.spawn(Position(9, 9));
+
+    let mut query = world
+        .query_mut::<(Mut<Position>, Velocity)>()
+        .where_(|velocity: &Velocity| velocity.0 > 0);
+
+    assert!(query.contains(fast), "Fast entity should match");
+    assert!(!query.contains(slow), "Slow entity should not match");
+    assert!(
+        query.get_mut(slow).is_none(),
+        "Slow entity should not match"
+    );
+
+    let (position, _) = query.get_mut(fast).unwrap();
+    position.1 = 0;
+
+    for (position, velocity) in query {
+        position.0 += velocity.0;
+    }
+
+    assert_eq!(world.get_component::<Position>(slow), Some(&Position(1, 2)));
+    assert_eq!(world.get_component::<Position>(fast), Some(&Position(8, 0)));
+    assert_eq!(
+        world.get_component::<Position>(named),
+        Some(&Position(11, 8))
+    );
+
+    let mut rows = Vec::new();
+
+    for (matched, (ids, positions)) in world
+        .query::<(EntityId, Position)>()
+        .where_(|position: &Position| position.0 > 5)
+        .chunks()
+    {
+        for row in matched {
+            rows.push((ids[row], positions[row]));
+        }
+    }
+
+    rows.sort_by_key(|(id, _)| *id);
+    assert_eq!(
+        rows,
+        vec![
+            (fast, Position(8, 0)),
+            (named, Position(11, 8)),
+            (still, Position(9, 9))
+        ],
+        "Chunks should return the rows satisfying the predicate"
+    );
+
+    let mut state = QueryState::<(EntityId, Mut<Velocity>), Without<Name>>::new(&world);
+
+    for (_, velocity) in state
+        .where_(|position: &Position| position.0 < 5)
+        .iter_mut(&mut world)
+    {
+        velocity.1 = 1;
+    }
+
+    let ids: Vec<_> = state
+        .where_(|velocity: &Velocity| velocity.1 == 1)
+        .iter_mut(&mut world)
+        .map(|(id, _)| id)
+        .collect();
+
+    assert_eq!(ids, vec![slow], "State predicates should filter rows");
+}
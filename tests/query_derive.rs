@@ -0,0 +1,136 @@
+use lemon_ecs::{
+    entities::EntityId,
+    macros::{QueryData, QueryFilter},
+    query::{QueryState, With, Without},
+    world::World,
+};
+
+mod common;
+use common::components::{Name, Position, Velocity};
+
+#[derive(QueryData)]
+struct Mover<'w> {
+    id: EntityId,
+    position: &'w mut Position,
+    velocity: &'w Velocity,
+    name: Option<&'w Name>,
+}
+
+#[derive(QueryData)]
+struct Named<'w>(&'w Position, Option<&'w Name>);
+
+type PositionMut<'w> = &'w mut Position;
+type VelocityRef<'w> = &'w Velocity;
+
+#[derive(QueryData)]
+struct AliasedMover<'w> {
+    position: PositionMut<'w>,
+    velocity: VelocityRef<'w>,
+}
+
+#[derive(QueryData)]
+struct AliasedVelocity<'w>(VelocityRef<'w>);
+
+#[derive(QueryFilter)]
+#[allow(dead_code)]
+struct Moving {
+    velocity: With<Velocity>,
+    named: Without<Name>,
+}
+
+#[test]
+pub fn derive_query_data() {
+    let mut world = World::default();
+
+    let entity_a = world.spawn((Position(0, 0), Velocity(1, 2)));
+    let entity_b = world.spawn((Position(0, 0), Velocity(3, 4), Name("B".into())));
+    world.spawn(Position(5, 5));
+
+    let mut query = world.query_mut::<Mover>();
+
+    let mover = query.get_mut(entity_b).unwrap();
+    assert_eq!(mover.id, entity_b, "Entity should be entity_b");
+    assert_eq!(mover.name.unwrap().0, "B", "Name should be retrieved");
+
+    let mut state = QueryState::<Mover>::new(&world);
+    let mut count = 0;
+
+    for mover in state.iter_mut(&mut world) {
+        mover.position.0 += mover.velocity.0;
+        mover.position.1 += mover.velocity.1;
+
+        assert_eq!(mover.name.is_some(), mover.id == entity_b);
+        count += 1;
+    }
+
+    assert_eq!(count, 2, "Entities without Velocity should not match");
+
+    let mut named: Vec<_> = world
+        .query::<Named>()
+        .into_iter()
+        .map(|Named(position, name)| (*position, name.map(|name| name.0.as_str())))
+        .collect();
+
+    named.sort_by_key(|(position, _)| (position.0, position.1));
+
+    assert_eq!(
+        named,
+        vec![
+            (Position(1, 2), None),
+            (Position(3, 4), Some("B")),
+            (Position(5, 5), None)
+        ]
+    );
+
+    assert_eq!(
+        world.query::<Named>().get(entity_a).unwrap().0,
+        &Position(1, 2)
+    );
+}
+
+#[test]
+pub fn derive_query_filter() {
+    let mut world = World::default();
+
+    let entity_a = world.spawn((Position(1, 2), Velocity(1, 1)));
+    world.spawn((Position(3, 4), Velocity(1, 1), Name("Frozen".into())));
+    world.spawn(Position(5, 6));
+
+    let ids: Vec<_> = world
+        .query::<(EntityId, Moving)>()
+        .into_iter()
+        .map(|(id, ())| id)
+        .collect();
+
+    assert_eq!(ids, vec![entity_a], "Only entity a should match");
+
+    let positions: Vec<_> = world.query::<Position>().filter::<Moving>().collect();
+
+    assert_eq!(
+        positions,
+        vec![&Position(1, 2)],
+        "Only entity a should match"
+    );
+}
+
+#[test]
+pub fn derive_query_data_aliases() {
+    let mut world = World::default();
+
+    let entity = world.spawn((Position(1, 2), Velocity(3, 4)));
+
+    let mut query = world.query_mut::<AliasedMover>();
+    let mover = query.get_mut(entity).unwrap();
+    mover.position.0 += mover.velocity.0;
+
+    assert_eq!(
+        world.get_component::<Position>(entity),
+        Some(&Position(4, 2)),
+        "Aliased mutable reference should be writable"
+    );
+    assert_eq!(
+        world.query::<AliasedVelocity>().get(entity).unwrap().0,
+        &Velocity(3, 4),
+        "Aliased shared reference should be read-only"
+    );
+}
@@ -0,0 +1,119 @@
+use std::{cell::Cell, rc::Rc};
+
+use lemon_ecs::world::World;
+
+mod common;
+use common::components::Position;
+
+#[derive(Default, Debug, PartialEq, Eq)]
+struct Score(u32);
+
+#[test]
+pub fn resource_lifecycle() {
+    let mut world = World::default();
+
+    assert!(
+        !world.contains_resource::<Score>(),
+        "Score should not exist"
+    );
+
+    world.init_resource::<Score>().0 += 2;
+    world.init_resource::<Score>().0 += 3;
+    assert_eq!(
+        world.get_resource::<Score>(),
+        Some(&Score(5)),
+        "Score should only be initialized once"
+    );
+
+    assert_eq!(world.remove_resource::<Score>(), Some(Score(5)));
+    assert!(
+        !world.contains_resource::<Score>(),
+        "Score should be removed"
+    );
+}
+
+#[test]
+pub fn resource_scope() {
+    let mut world = World::default();
+    world.insert_resource(Score(0));
+    world.spawn(Position(1, 2));
+    world.spawn(Position(3, 4));
+
+    let count = world.resource_scope(|world, score: &mut Score| {
+        assert!(
+            !world.contains_resource::<Score>(),
+            "Score should be lifted out of the world"
+        );
+
+        for position in world.query::<Position>() {
+            score.0 += position.0;
+        }
+
+        world.query::<Position>().into_iter().count()
+    });
+
+    assert_eq!(count, 2, "Scope should return the closure's result");
+    assert_eq!(
+        world.get_resource::<Score>(),
+        Some(&Score(4)),
+        "Score should be put back"
+    );
+}
+
+#[test]
+pub fn buffered_resource_commands() {
+    let mut world = World::default();
+
+    let mut buffer = world.buffer();
+    buffer.insert_resource(Score(1));
+    buffer.modify_resource(|score: &mut Score| score.0 += 10);
+
+    // Closures only need to be `Send`, so they can capture a `Cell`.
+    let bonus = Cell::new(5);
+    buffer.modify_resource(move |score: &mut Score| score.0 += bonus.get());
+    world.apply_buffer(buffer);
+
+    assert_eq!(
+        world.get_resource::<Score>(),
+        Some(&Score(16)),
+        "Score should be modified after being inserted"
+    );
+
+    let mut buffer = world.buffer();
+    buffer.remove_resource::<Score>();
+    buffer.modify_resource(|score: &mut Score| score.0 += 10);
+    world.apply_buffer(buffer);
+
+    assert!(
+        !world.contains_resource::<Score>(),
+        "Score should be removed"
+    );
+}
+
+#[test]
+pub fn non_send_resources() {
+    let mut world = World::default();
+    let shared = Rc::new(Cell::new(1u32));
+
+    world.insert_non_send(shared.clone());
+    assert!(
+        world.is_owner_thread(),
+        "World should be created on this thread"
+    );
+
+    world.get_non_send_mut::<Rc<Cell<u32>>>().unwrap().set(5);
+    assert_eq!(shared.get(), 5, "Non-send resource should be shared");
+
+    assert!(
+        !world.contains_resource::<Rc<Cell<u32>>>(),
+        "Non-send resources should be stored separately"
+    );
+    assert!(
+        world.remove_non_send::<Rc<Cell<u32>>>().is_some(),
+        "Non-send resource should be removed"
+    );
+    assert!(
+        !world.contains_non_send::<Rc<Cell<u32>>>(),
+        "Non-send resource should not exist"
+    );
+}
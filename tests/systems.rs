@@ -1,5 +1,10 @@
 use lemon_ecs::{
-    buffer::WorldBuffer, engine::Engine, entities::EntityId, query::Without, world::World,
+    buffer::WorldBuffer,
+    engine::{AppExit, Engine},
+    entities::EntityId,
+    query::Without,
+    system::{ErrorAction, Local, System, SystemTicks},
+    world::World,
 };
 
 mod common;
@@ -83,3 +88,237 @@ pub fn engine_resource() {
 
     assert_eq!(counter.unwrap().count, 15, "Counter should be 15");
 }
+
+fn exit_system(world: &mut World) {
+    let counter = world.get_resource_mut::<Counter>().unwrap();
+    counter.increment();
+
+    if counter.count == 5 {
+        world.insert_resource(AppExit::Error(3));
+    }
+}
+
+#[test]
+pub fn engine_run_until_exit() {
+    let mut engine = Engine::default();
+    engine.insert_resource(Counter::new());
+    engine.push_system(exit_system);
+
+    assert_eq!(engine.run_until_exit(), AppExit::Error(3));
+    assert_eq!(engine.get_resource::<Counter>().unwrap().count, 5);
+    assert!(
+        engine.get_resource::<AppExit>().is_none(),
+        "AppExit should be consumed"
+    );
+
+    assert_eq!(engine.run_for(3), None, "Engine should not exit");
+    assert_eq!(engine.get_resource::<Counter>().unwrap().count, 8);
+}
+
+#[test]
+pub fn engine_custom_runner() {
+    let mut engine = Engine::default();
+    engine.insert_resource(Counter::new());
+
+    engine
+        .push_system(|world: &mut World| world.get_resource_mut::<Counter>().unwrap().increment())
+        .set_runner(|engine| {
+            engine.run();
+            engine.run();
+            AppExit::Success
+        });
+
+    assert_eq!(engine.start(), AppExit::Success);
+    assert_eq!(engine.get_resource::<Counter>().unwrap().count, 2);
+}
+
+#[derive(Debug)]
+struct OutOfRange(u32);
+
+impl std::fmt::Display for OutOfRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} is out of range", self.0)
+    }
+}
+
+impl std::error::Error for OutOfRange {}
+
+fn fallible_system(world: &mut World) -> Result<(), OutOfRange> {
+    let counter = world.get_resource_mut::<Counter>().unwrap();
+    counter.increment();
+
+    if counter.count.is_multiple_of(2) {
+        return Err(OutOfRange(counter.count));
+    }
+
+    Ok(())
+}
+
+#[test]
+pub fn engine_system_errors() {
+    let mut engine = Engine::default();
+    engine.insert_resource(Counter::new());
+
+    engine
+        .push_system(fallible_system)
+        .push_system(fallible_system)
+        .set_error_handler(|_| ErrorAction::Continue);
+
+    engine.run();
+
+    assert_eq!(engine.errors().len(), 1, "Second system should fail");
+    assert_eq!(engine.errors()[0].error().to_string(), "2 is out of range");
+    assert!(engine.errors()[0].system().contains("fallible_system"));
+
+    engine.set_error_handler(|_| ErrorAction::Abort);
+    engine.run();
+
+    assert_eq!(engine.get_resource::<Counter>().unwrap().count, 4);
+    assert_eq!(engine.errors().len(), 1, "Run should abort after failing");
+
+    engine.set_error_handler(|_| ErrorAction::Disable);
+    engine.run();
+    engine.run();
+
+    assert_eq!(
+        engine.get_resource::<Counter>().unwrap().count,
+        7,
+        "Failing system should be disabled"
+    );
+}
+
+fn panic_system(_: &mut World) {
+    panic!("bad system");
+}
+
+#[test]
+pub fn engine_catch_panics() {
+    let mut engine = Engine::default();
+    engine.insert_resource(Counter::new());
+
+    engine
+        .push_system(panic_system)
+        .push_system(|world: &mut World| world.get_resource_mut::<Counter>().unwrap().increment())
+        .set_error_handler(|_| ErrorAction::Continue)
+        .set_catch_panics(true);
+
+    engine.run();
+
+    assert_eq!(engine.get_resource::<Counter>().unwrap().count, 1);
+    assert!(engine.errors()[0].is_panic(), "Error should be a panic");
+    assert_eq!(
+        engine.errors()[0].error().to_string(),
+        "panicked: bad system"
+    );
+}
+
+#[test]
+pub fn engine_run_once_errors() {
+    let mut engine = Engine::default();
+    engine.insert_resource(Counter::new());
+
+    engine
+        .push_system(|world: &mut World| world.get_resource_mut::<Counter>().unwrap().increment())
+        .set_error_handler(|_| ErrorAction::Abort)
+        .set_catch_panics(true)
+        .run_once(panic_system);
+
+    assert!(engine.errors()[0].is_panic(), "Panic should be caught");
+    assert_eq!(
+        engine.run_for(3),
+        Some(AppExit::Error(1)),
+        "Engine should exit after an aborted run_once"
+    );
+    assert_eq!(engine.get_resource::<Counter>().unwrap().count, 0);
+
+    assert_eq!(engine.run_for(3), None, "Engine should not exit");
+    assert_eq!(engine.get_resource::<Counter>().unwrap().count, 3);
+}
+
+#[test]
+pub fn closure_system() {
+    let mut world = World::default();
+    world.insert_resource(Counter::new());
+
+    let mut systems: Vec<Box<dyn System>> = vec![
+        Box::new(|world: &mut World| world.get_resource_mut::<Counter>().unwrap().increment()),
+        Box::new(fallible_system),
+        Box::new(|world: &mut World| world.get_resource_mut::<Counter>().unwrap().increment()),
+    ];
+
+    assert!(
+        systems.run(&mut world).is_err(),
+        "Second system should fail"
+    );
+    assert_eq!(
+        world.get_resource::<Counter>().unwrap().count,
+        2,
+        "Systems after the failing one should not run"
+    );
+}
+
+fn local_system(world: &mut World, mut runs: Local<u32>, ticks: SystemTicks) {
+    *runs += 1;
+
+    assert_eq!(ticks.is_first_run(), *runs == 1);
+    assert!(ticks
+        .last_run()
+        .is_none_or(|last_run| last_run < ticks.this_run()));
+
+    world.get_resource_mut::<Counter>().unwrap().count += *runs;
+}
+
+#[test]
+pub fn engine_local_state() {
+    let mut engine = Engine::default();
+    engine.insert_resource(Counter::new());
+
+    engine.push_system(local_system).push_system(local_system);
+
+    engine.run();
+    engine.run();
+    engine.run();
+
+    // Each system keeps its own count: 2 * (1 + 2 + 3)
+    assert_eq!(engine.get_resource::<Counter>().unwrap().count, 12);
+}
+
+#[test]
+pub fn system_change_ticks() {
+    let mut engine = Engine::default();
+
+    let entity_a = engine.spawn(Position(1, 2));
+    let entity_b = engine.spawn(Position(3, 4));
+
+    engine.query_changed::<Position>();
+
+    engine
+        .run_once(move |world: &mut World| world.insert(entity_a, Position(5, 6)))
+        .run_once(move |world: &mut World| world.insert(entity_b, Position(7, 8)));
+
+    assert_eq!(
+        engine.change_tick(),
+        2,
+        "Each system should advance the tick"
+    );
+
+    let query = engine.query_changed::<Position>();
+
+    assert_eq!(
+        query.tick(entity_a),
+        Some(1),
+        "First change should be tick 1"
+    );
+    assert_eq!(
+        query.tick(entity_b),
+        Some(2),
+        "Second change should be tick 2"
+    );
+
+    let ticks: Vec<_> = query
+        .into_iter()
+        .map(|snapshot| (snapshot.id(), snapshot.tick()))
+        .collect();
+
+    assert_eq!(ticks, vec![(entity_a, 1), (entity_b, 2)]);
+}
@@ -0,0 +1,123 @@
+use std::{
+    marker::PhantomData,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use lemon_ecs::{engine::Engine, world::World};
+
+mod common;
+use common::components::{Position, Velocity};
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+fn assert_send<T: Send>() {}
+
+#[test]
+pub fn world_is_thread_safe() {
+    assert_send_sync::<World>();
+    assert_send::<Engine>();
+}
+
+#[test]
+pub fn shared_world_reads() {
+    let mut world = World::default();
+
+    for i in 0..100 {
+        let entity = world.spawn(Position(i, i));
+
+        if i % 2 == 0 {
+            world.insert(entity, Velocity(1, 1));
+        }
+    }
+
+    let world = &world;
+
+    let sums: Vec<u32> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                scope.spawn(move || {
+                    world
+                        .query::<(Position, Velocity)>()
+                        .into_iter()
+                        .map(|(position, _)| position.0)
+                        .sum()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+
+    assert_eq!(sums, vec![2450; 4], "Every thread should see every entity");
+}
+
+#[test]
+pub fn world_moved_to_worker_thread() {
+    let mut world = World::default();
+    world.insert_non_send(Rc::new(5));
+    world.insert_resource(10u32);
+
+    let world = thread::spawn(move || {
+        let entity = world.spawn(Position(1, 2));
+        assert_eq!(world.get_resource::<u32>(), Some(&10));
+
+        let non_send = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            world.get_non_send::<Rc<i32>>().is_some()
+        }));
+        assert!(
+            non_send.is_err(),
+            "Non-send resources should not be accessible from another thread"
+        );
+
+        (world, entity)
+    });
+
+    let (world, entity) = world.join().unwrap();
+
+    assert_eq!(
+        world.get_component::<Position>(entity),
+        Some(&Position(1, 2)),
+        "Entity spawned on the worker should exist"
+    );
+    assert_eq!(
+        world.get_non_send::<Rc<i32>>().map(|value| **value),
+        Some(5),
+        "Non-send resources should be accessible on the original thread"
+    );
+}
+
+/// Sets the flag when dropped, and isn't `Send` because of the raw pointer marker.
+struct DropFlag(Arc<AtomicBool>, PhantomData<*const ()>);
+
+impl Drop for DropFlag {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+#[test]
+pub fn world_dropped_on_worker_thread() {
+    let dropped = Arc::new(AtomicBool::new(false));
+
+    let mut world = World::default();
+    world.insert_non_send(DropFlag(dropped.clone(), PhantomData));
+
+    let worker = thread::spawn(move || drop(world));
+
+    assert!(
+        worker.join().is_ok(),
+        "Dropping a world on another thread should not panic"
+    );
+    assert!(
+        !dropped.load(Ordering::Relaxed),
+        "Non-send resources should be leaked rather than dropped on another thread"
+    );
+}
@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use lemon_ecs::{
+    engine::Engine,
+    time::{Clock, FixedTime, ManualClock, Time},
+    world::World,
+};
+
+#[derive(Default)]
+struct Steps(u32);
+
+fn step_system(world: &mut World) {
+    world.get_resource_mut::<Steps>().unwrap().0 += 1;
+}
+
+fn fixed_engine(clock: &ManualClock) -> Engine {
+    let mut engine = Engine::default();
+
+    engine
+        .set_clock(clock.clone())
+        .set_fixed_timestep(Duration::from_millis(10))
+        .push_fixed_system(step_system);
+
+    engine.insert_resource(Steps::default());
+    engine.run();
+    engine
+}
+
+#[test]
+pub fn fixed_timestep_accumulator() {
+    let clock = ManualClock::new();
+    let mut engine = fixed_engine(&clock);
+
+    clock.advance(Duration::from_millis(25));
+    engine.run();
+
+    assert_eq!(
+        engine.get_resource::<Steps>().unwrap().0,
+        2,
+        "Should step twice"
+    );
+
+    let fixed_time = engine.get_resource::<FixedTime>().unwrap();
+    assert_eq!(fixed_time.accumulator(), Duration::from_millis(5));
+
+    clock.advance(Duration::from_millis(5));
+    engine.run();
+
+    assert_eq!(
+        engine.get_resource::<Steps>().unwrap().0,
+        3,
+        "Should step once"
+    );
+
+    engine.run();
+
+    assert_eq!(
+        engine.get_resource::<Steps>().unwrap().0,
+        3,
+        "Should not step"
+    );
+    assert_eq!(engine.get_resource::<Time>().unwrap().ticks(), 4);
+}
+
+#[test]
+pub fn fixed_timestep_max_steps() {
+    let clock = ManualClock::new();
+    let mut engine = fixed_engine(&clock);
+
+    engine
+        .get_resource_mut::<FixedTime>()
+        .unwrap()
+        .set_max_steps(3);
+
+    clock.advance(Duration::from_millis(105));
+    engine.run();
+
+    assert_eq!(
+        engine.get_resource::<Steps>().unwrap().0,
+        3,
+        "Should step 3 times"
+    );
+
+    let fixed_time = engine.get_resource::<FixedTime>().unwrap();
+    assert_eq!(fixed_time.accumulator(), Duration::from_millis(5));
+}
+
+#[test]
+pub fn time_pause_and_scale() {
+    let clock = ManualClock::new();
+    let mut engine = fixed_engine(&clock);
+
+    engine.get_resource_mut::<Time>().unwrap().pause();
+
+    clock.advance(Duration::from_millis(50));
+    engine.run();
+
+    let time = engine.get_resource::<Time>().unwrap();
+
+    assert_eq!(
+        time.delta(),
+        Duration::ZERO,
+        "Paused time should not advance"
+    );
+    assert_eq!(time.real_delta(), Duration::from_millis(50));
+    assert_eq!(
+        engine.get_resource::<Steps>().unwrap().0,
+        0,
+        "Should not step"
+    );
+
+    let time = engine.get_resource_mut::<Time>().unwrap();
+    time.unpause();
+    time.set_relative_speed(2.0);
+
+    clock.advance(Duration::from_millis(10));
+    engine.run();
+
+    let time = engine.get_resource::<Time>().unwrap();
+
+    assert_eq!(time.delta(), Duration::from_millis(20));
+    assert_eq!(time.elapsed(), Duration::from_millis(20));
+    assert_eq!(time.real_elapsed(), Duration::from_millis(60));
+    assert_eq!(
+        engine.get_resource::<Steps>().unwrap().0,
+        2,
+        "Should step twice"
+    );
+}
+
+#[test]
+pub fn tick_rate_pacing() {
+    let clock = ManualClock::new();
+    let mut engine = fixed_engine(&clock);
+
+    engine.set_tick_rate(Some(50.0));
+
+    assert_eq!(engine.run_for(5), None, "Engine should not exit");
+
+    let time = engine.get_resource::<Time>().unwrap();
+
+    assert_eq!(time.elapsed(), Duration::from_millis(80));
+    assert_eq!(clock.now(), Duration::from_millis(100));
+}
+
+#[test]
+#[should_panic(expected = "cannot be set to an earlier time")]
+pub fn manual_clock_rewind() {
+    let clock = ManualClock::new();
+
+    clock.set(Duration::from_millis(20));
+    clock.set(Duration::from_millis(10));
+}